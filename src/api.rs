@@ -1,5 +1,6 @@
 use peroxide::fuga::*;
-use time::{format_description, OffsetDateTime};
+use serde::Deserialize;
+use time::{format_description, macros::format_description, Date, OffsetDateTime, Weekday};
 use yahoo_finance_api::{self as yahoo, YResponse};
 
 pub async fn download_stocks(
@@ -57,6 +58,23 @@ impl Chart {
     pub fn get_adj_close(&self) -> f64 {
         self.adj_close
     }
+
+    /// Open, high, low & close scaled by `adj_close / close` (split & dividend adjusted)
+    pub fn adjusted(&self) -> Self {
+        let ratio = if self.close != 0f64 {
+            self.adj_close / self.close
+        } else {
+            1f64
+        };
+        Self {
+            open: self.open * ratio,
+            high: self.high * ratio,
+            low: self.low * ratio,
+            close: self.adj_close,
+            volume: self.volume,
+            adj_close: self.adj_close,
+        }
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -99,6 +117,57 @@ impl HistoricalChart {
         self.chart.iter().map(|x| x.get_adj_close()).collect::<Vec<f64>>()
     }
 
+    /// Index of the resampled bar for each bar
+    pub fn period_index(
+        &self,
+        timeframe: Timeframe,
+    ) -> Result<Vec<usize>, Box<dyn std::error::Error>> {
+        let mut idx = vec![0usize; self.date.len()];
+        let mut prev_key = None;
+        let mut k = 0usize;
+        for (i, date) in self.date.iter().enumerate() {
+            let key = timeframe.key(date)?;
+            if prev_key.is_some() && prev_key != Some(key) {
+                k += 1;
+            }
+            idx[i] = k;
+            prev_key = Some(key);
+        }
+        Ok(idx)
+    }
+
+    /// Aggregate bars into a higher timeframe
+    ///
+    /// - Each bar is dated by the last date of its period (when the bar is closed)
+    pub fn resample(
+        &self,
+        timeframe: Timeframe,
+    ) -> Result<HistoricalChart, Box<dyn std::error::Error>> {
+        let idx = self.period_index(timeframe)?;
+        let mut date: Vec<String> = vec![];
+        let mut chart: Vec<Chart> = vec![];
+        for (i, &k) in idx.iter().enumerate() {
+            let c = self.chart[i];
+            if k == chart.len() {
+                date.push(self.date[i].clone());
+                chart.push(c);
+            } else {
+                let agg = &mut chart[k];
+                agg.high = agg.high.max(c.high);
+                agg.low = agg.low.min(c.low);
+                agg.close = c.close;
+                agg.volume += c.volume;
+                agg.adj_close = c.adj_close;
+                date[k] = self.date[i].clone();
+            }
+        }
+        Ok(HistoricalChart {
+            symbol: self.symbol.clone(),
+            date,
+            chart,
+        })
+    }
+
     pub fn to_dataframe(&self) -> DataFrame {
         let mut df = DataFrame::new(vec![]);
         df.push("date", Series::new(self.get_dates().clone()));
@@ -112,6 +181,52 @@ impl HistoricalChart {
     }
 }
 
+/// Parse `YYYY-MM-DD`
+pub fn parse_date(date: &str) -> Result<Date, Box<dyn std::error::Error>> {
+    let fmt = format_description!("[year]-[month]-[day]");
+    Ok(Date::parse(date, &fmt)?)
+}
+
+/// Next weekday after `date` (exchange holidays are not known)
+pub fn next_business_day(date: Date) -> Date {
+    let mut next = date.next_day().unwrap_or(date);
+    while matches!(next.weekday(), Weekday::Saturday | Weekday::Sunday) {
+        next = next.next_day().unwrap_or(next);
+    }
+    next
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Timeframe {
+    Daily,
+    Weekly,
+    Monthly,
+    Quarterly,
+    Yearly,
+}
+
+impl Timeframe {
+    /// Key which is shared by all dates of the same period
+    pub fn key(&self, date: &str) -> Result<(i32, u16), Box<dyn std::error::Error>> {
+        Ok(self.key_of(parse_date(date)?))
+    }
+
+    /// `key` of a parsed date
+    pub fn key_of(&self, date: Date) -> (i32, u16) {
+        match self {
+            Timeframe::Daily => (date.year(), date.ordinal()),
+            Timeframe::Weekly => {
+                let (year, week, _) = date.to_iso_week_date();
+                (year, week as u16)
+            }
+            Timeframe::Monthly => (date.year(), date.month() as u16),
+            Timeframe::Quarterly => (date.year(), (date.month() as u16 - 1) / 3),
+            Timeframe::Yearly => (date.year(), 0),
+        }
+    }
+}
+
 pub trait Quote {
     fn get_timestemp(&self) -> Vec<String>;
     fn get_open(&self) -> Vec<f64>;
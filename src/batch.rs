@@ -0,0 +1,263 @@
+use crate::api::HistoricalChart;
+use crate::ta::*;
+use peroxide::fuga::*;
+use std::collections::BTreeSet;
+
+// ┌──────────────────────────────────────────────────────────┐
+//  Indicator
+// └──────────────────────────────────────────────────────────┘
+/// Indicator with its parameters, evaluated on the close price
+#[derive(Debug, Clone, Copy)]
+pub enum Indicator {
+    Close,
+    Volume,
+    VolumeSMA(usize),
+    MA(MAType, usize),
+    RSI(usize),
+    MACD(usize, usize, usize),
+    ATR(usize),
+    ADX(usize),
+    Stochastic(usize, usize),
+    CCI(usize),
+    WilliamsR(usize),
+    ROC(usize),
+    ZScore(usize),
+    SuperTrend(usize, f64),
+    Aroon(usize),
+    Custom(&'static str, fn(&HistoricalChart) -> Vec<f64>),
+}
+
+impl Indicator {
+    /// Column names of the indicator outputs
+    pub fn columns(&self) -> Vec<String> {
+        match self {
+            Indicator::Close => vec!["close".to_string()],
+            Indicator::Volume => vec!["volume".to_string()],
+            Indicator::VolumeSMA(p) => vec![format!("volume_sma_{}", p)],
+            Indicator::MA(ma_type, p) => vec![format!("{:?}_{}", ma_type, p).to_lowercase()],
+            Indicator::RSI(p) => vec![format!("rsi_{}", p)],
+            Indicator::MACD(p1, p2, p3) => {
+                let suffix = format!("{}_{}_{}", p1, p2, p3);
+                vec![
+                    format!("macd_{}", suffix),
+                    format!("macd_signal_{}", suffix),
+                    format!("macd_hist_{}", suffix),
+                ]
+            }
+            Indicator::ATR(p) => vec![format!("atr_{}", p)],
+            Indicator::ADX(p) => vec![
+                format!("adx_{}", p),
+                format!("di_plus_{}", p),
+                format!("di_minus_{}", p),
+            ],
+            Indicator::Stochastic(p, s) => vec![
+                format!("stoch_k_{}_{}", p, s),
+                format!("stoch_d_{}_{}", p, s),
+            ],
+            Indicator::CCI(p) => vec![format!("cci_{}", p)],
+            Indicator::WilliamsR(p) => vec![format!("williams_r_{}", p)],
+            Indicator::ROC(p) => vec![format!("roc_{}", p)],
+            Indicator::ZScore(p) => vec![format!("zscore_{}", p)],
+            Indicator::SuperTrend(p, m) => vec![
+                format!("supertrend_{}_{}", p, m),
+                format!("supertrend_dir_{}_{}", p, m),
+            ],
+            Indicator::Aroon(p) => vec![
+                format!("aroon_up_{}", p),
+                format!("aroon_down_{}", p),
+                format!("aroon_osc_{}", p),
+            ],
+            Indicator::Custom(name, _) => vec![name.to_string()],
+        }
+    }
+
+    /// Indicator outputs in the order of `columns`
+    pub fn compute(&self, hist: &HistoricalChart) -> Vec<Vec<f64>> {
+        let high = hist.get_high_vec();
+        let low = hist.get_low_vec();
+        let close = hist.get_close_vec();
+        let volume = hist
+            .get_volume_vec()
+            .into_iter()
+            .map(|x| x as f64)
+            .collect::<Vec<f64>>();
+        match *self {
+            Indicator::Close => vec![close],
+            Indicator::Volume => vec![volume],
+            Indicator::VolumeSMA(p) => vec![sma(&volume, p)],
+            Indicator::MA(ma_type, p) => vec![ma(&close, p, ma_type)],
+            Indicator::RSI(p) => vec![wilder_rsi(&close, p)],
+            Indicator::MACD(p1, p2, p3) => {
+                let (m, s, h) = macd(&close, p1, p2, p3, MAType::EMA);
+                vec![m, s, h]
+            }
+            Indicator::ATR(p) => vec![atr(&high, &low, &close, p)],
+            Indicator::ADX(p) => {
+                let (adx, di_plus, di_minus) = adx_dmi(&high, &low, &close, p);
+                vec![adx, di_plus, di_minus]
+            }
+            Indicator::Stochastic(p, s) => {
+                let (k, d) = stochastic(&high, &low, &close, p, s);
+                vec![k, d]
+            }
+            Indicator::CCI(p) => vec![cci(&high, &low, &close, p)],
+            Indicator::WilliamsR(p) => vec![williams_r(&high, &low, &close, p)],
+            Indicator::ROC(p) => vec![roc(&close, p)],
+            Indicator::ZScore(p) => vec![zscore(&close, p)],
+            Indicator::SuperTrend(p, m) => {
+                let (st, dir) = supertrend(&high, &low, &close, p, m);
+                vec![st, dir]
+            }
+            Indicator::Aroon(p) => {
+                let (up, down, osc) = aroon(&high, &low, p);
+                vec![up, down, osc]
+            }
+            Indicator::Custom(_, f) => vec![f(hist)],
+        }
+    }
+}
+
+// ┌──────────────────────────────────────────────────────────┐
+//  Batch computation
+// └──────────────────────────────────────────────────────────┘
+/// Indicator columns of a single symbol
+#[derive(Debug, Clone)]
+pub struct IndicatorTable {
+    pub symbol: String,
+    pub date: Vec<String>,
+    pub header: Vec<String>,
+    pub data: Vec<Vec<f64>>,
+}
+
+impl IndicatorTable {
+    pub fn new(hist: &HistoricalChart, indicators: &[Indicator]) -> Self {
+        let mut header = vec![];
+        let mut data = vec![];
+        for indicator in indicators.iter() {
+            header.extend(indicator.columns());
+            data.extend(indicator.compute(hist));
+        }
+        Self {
+            symbol: hist.symbol.clone(),
+            date: hist.get_dates().clone(),
+            header,
+            data,
+        }
+    }
+
+    pub fn get(&self, column: &str) -> Option<&Vec<f64>> {
+        self.header
+            .iter()
+            .position(|x| x == column)
+            .map(|i| &self.data[i])
+    }
+
+    pub fn to_dataframe(&self) -> DataFrame {
+        let mut df = DataFrame::new(vec![]);
+        df.push("date", Series::new(self.date.clone()));
+        for (name, col) in self.header.iter().zip(self.data.iter()) {
+            df.push(name, Series::new(col.clone()));
+        }
+        df
+    }
+}
+
+/// Compute indicators for every chart in parallel across CPU cores
+pub fn compute_batch(hists: &[HistoricalChart], indicators: &[Indicator]) -> Vec<IndicatorTable> {
+    if hists.is_empty() {
+        return vec![];
+    }
+    let n_threads = std::thread::available_parallelism()
+        .map(|n| n.get())
+        .unwrap_or(1);
+    let chunk_size = hists.len().div_ceil(n_threads);
+    std::thread::scope(|s| {
+        let handles = hists
+            .chunks(chunk_size)
+            .map(|chunk| {
+                s.spawn(move || {
+                    chunk
+                        .iter()
+                        .map(|hist| IndicatorTable::new(hist, indicators))
+                        .collect::<Vec<IndicatorTable>>()
+                })
+            })
+            .collect::<Vec<_>>();
+        handles
+            .into_iter()
+            .flat_map(|h| h.join().unwrap())
+            .collect()
+    })
+}
+
+/// Layout of the batch output
+///
+/// - Long : one row per (symbol, date), one column per indicator
+/// - Wide : one row per date, one `symbol:indicator` column per pair
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Layout {
+    Long,
+    Wide,
+}
+
+pub fn batch_to_dataframe(tables: &[IndicatorTable], layout: Layout) -> DataFrame {
+    let mut df = DataFrame::new(vec![]);
+    match layout {
+        Layout::Long => {
+            let header = tables.first().map(|t| t.header.clone()).unwrap_or_default();
+            let mut symbol = vec![];
+            let mut date = vec![];
+            let mut data = vec![vec![]; header.len()];
+            for table in tables.iter() {
+                symbol.extend(vec![table.symbol.clone(); table.date.len()]);
+                date.extend(table.date.iter().cloned());
+                for (col, v) in data.iter_mut().zip(table.data.iter()) {
+                    col.extend(v.iter().cloned());
+                }
+            }
+            df.push("symbol", Series::new(symbol));
+            df.push("date", Series::new(date));
+            for (name, col) in header.iter().zip(data) {
+                df.push(name, Series::new(col));
+            }
+        }
+        Layout::Wide => {
+            // Union of dates; missing values are NaN
+            let date = tables
+                .iter()
+                .flat_map(|t| t.date.iter().cloned())
+                .collect::<BTreeSet<String>>()
+                .into_iter()
+                .collect::<Vec<String>>();
+            df.push("date", Series::new(date.clone()));
+            for table in tables.iter() {
+                let row = table
+                    .date
+                    .iter()
+                    .map(|d| date.binary_search(d).unwrap())
+                    .collect::<Vec<usize>>();
+                for (name, v) in table.header.iter().zip(table.data.iter()) {
+                    let mut col = vec![f64::NAN; date.len()];
+                    for (&r, &x) in row.iter().zip(v.iter()) {
+                        col[r] = x;
+                    }
+                    df.push(&format!("{}:{}", table.symbol, name), Series::new(col));
+                }
+            }
+        }
+    }
+    df
+}
+
+/// Compute indicators in parallel and write a single parquet file
+pub fn write_batch_parquet(
+    hists: &[HistoricalChart],
+    indicators: &[Indicator],
+    layout: Layout,
+    path: &str,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let tables = compute_batch(hists, indicators);
+    let df = batch_to_dataframe(&tables, layout);
+    df.write_parquet(path, CompressionOptions::Uncompressed)?;
+    Ok(())
+}
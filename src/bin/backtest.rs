@@ -0,0 +1,31 @@
+use fmp::config::BacktestConfig;
+use peroxide::fuga::*;
+use std::env::args;
+
+// Usage: cargo run --bin backtest -- config/trade_test_07.toml
+#[tokio::main]
+async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let path = args()
+        .nth(1)
+        .ok_or("usage: backtest <config.toml|config.yaml>")?;
+    let config = BacktestConfig::from_file(&path)?;
+
+    let mut bt = config.build_backtester().await?;
+    let report = bt.run(config.rolling_window);
+    let df = report.to_dataframe();
+    let dg = report.to_report();
+
+    df.print();
+    dg.print();
+
+    df.write_parquet(
+        &format!("./data/{}.parquet", config.name),
+        CompressionOptions::Uncompressed,
+    )?;
+    dg.write_parquet(
+        &format!("./data/{}_report.parquet", config.name),
+        CompressionOptions::Uncompressed,
+    )?;
+
+    Ok(())
+}
@@ -0,0 +1,46 @@
+use fmp::api::download_stocks;
+use fmp::batch::{batch_to_dataframe, compute_batch, Indicator, Layout};
+use fmp::ta::MAType;
+use peroxide::fuga::*;
+use std::env::args;
+
+#[tokio::main]
+async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let symbols = args().skip(1).collect::<Vec<String>>();
+    let symbols = if symbols.is_empty() {
+        vec![
+            "005930.KS".to_string(),
+            "000660.KS".to_string(),
+            "035420.KS".to_string(),
+        ]
+    } else {
+        symbols
+    };
+    let from = "2022-01-09 00:00:00 +09";
+    let to = "2023-10-12 00:00:00 +09";
+
+    let stocks = download_stocks(&symbols, from, to).await?;
+    let indicators = vec![
+        Indicator::Close,
+        Indicator::MA(MAType::SMA, 20),
+        Indicator::MA(MAType::EMA, 20),
+        Indicator::RSI(14),
+        Indicator::MACD(12, 26, 9),
+        Indicator::ADX(14),
+        Indicator::Stochastic(14, 3),
+        Indicator::CCI(20),
+    ];
+
+    // Compute once, write both layouts
+    let tables = compute_batch(&stocks, &indicators);
+    batch_to_dataframe(&tables, Layout::Long).write_parquet(
+        "./data/batch_long.parquet",
+        CompressionOptions::Uncompressed,
+    )?;
+    batch_to_dataframe(&tables, Layout::Wide).write_parquet(
+        "./data/batch_wide.parquet",
+        CompressionOptions::Uncompressed,
+    )?;
+
+    Ok(())
+}
@@ -1,5 +1,5 @@
 use fmp::api::download_stocks;
-use fmp::ta::{adx_dmi, cci, divergence, ema, macd, rsi, sma, stochastic, wma};
+use fmp::ta::{adx_dmi, cci, ema, macd, peak_envelope, rsi, sma, stochastic, wma, MAType};
 use peroxide::fuga::*;
 use std::env::args;
 
@@ -23,15 +23,14 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         .zip(low.iter())
         .map(|((c, h), l)| (c + h + l) / 3f64)
         .collect::<Vec<f64>>();
-    let (tp_div, tp_slope) = divergence(&tp);
+    let (tp_div, tp_slope) = peak_envelope(&tp)?;
     let sma_ = sma(&tp, 20);
     let ema_ = ema(&tp, 20);
     let wma_ = wma(&tp, 20);
     let rsi_ = rsi(&close, 14);
     let rsi_signal = ema(&rsi_, 9);
-    let (rsi_div, rsi_slope) = divergence(&rsi_);
-    let macd_ = macd(&close, 12, 26);
-    let macd_signal = ema(&macd_, 9);
+    let (rsi_div, rsi_slope) = peak_envelope(&rsi_)?;
+    let (macd_, macd_signal, _) = macd(&close, 12, 26, 9, MAType::EMA);
     let (adx_, di_plus, di_minus) = adx_dmi(&high, &low, &close, 14);
     let (k, d) = stochastic(&high, &low, &close, 14, 3);
     let cci_ = cci(&high, &low, &close, 20);
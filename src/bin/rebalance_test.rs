@@ -1,5 +1,5 @@
 use fmp::strategy::{BuyAndHold, PeriodicRebalance, ThresholdRebalance};
-use fmp::trade::Backtester;
+use fmp::trade::{Backtester, MarketData};
 use peroxide::fuga::*;
 use std::collections::HashMap;
 use std::env::args;
@@ -51,6 +51,7 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     let sec_fees = vec![0.00015f64, 0.001, 0.001];
     let sec_fee = weights.dot(&sec_fees);
     let weights = HashMap::from_iter(symbols.clone().into_iter().zip(weights));
+    let market_data = MarketData::new(&symbols, from, to).await?;
 
     // ┌──────────────────────────────────────────────────────────┐
     //  1. Periodic Rebalancing
@@ -58,16 +59,14 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     let rebalance_period = if TESTNUM < 6 { PERIOD[TESTNUM] } else { 240 };
     let periodic_rebalance = PeriodicRebalance::new(rebalance_period);
     let bnh_periodic = BuyAndHold::new(weights.clone(), Box::new(periodic_rebalance));
-    let mut bt_periodic = Backtester::new(
+    let mut bt_periodic = Backtester::from_market_data(
         &symbols,
         init_balance,
         Box::new(bnh_periodic),
-        from,
-        to,
+        market_data.clone(),
         interest_rate,
         sec_fee,
-    )
-    .await?;
+    );
     let report_periodic = bt_periodic.run(120);
     let df_periodic = report_periodic.to_dataframe();
     let dg_periodic = report_periodic.to_report();
@@ -82,16 +81,14 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     };
     let threshold_rebalance = ThresholdRebalance::new(threshold, &weights);
     let bnh_threshold = BuyAndHold::new(weights.clone(), Box::new(threshold_rebalance));
-    let mut bt_threshold = Backtester::new(
+    let mut bt_threshold = Backtester::from_market_data(
         &symbols,
         init_balance,
         Box::new(bnh_threshold),
-        from,
-        to,
+        market_data.clone(),
         interest_rate,
         sec_fee,
-    )
-    .await?;
+    );
     let report_threshold = bt_threshold.run(120);
     let df_threshold = report_threshold.to_dataframe();
     let dg_threshold = report_threshold.to_report();
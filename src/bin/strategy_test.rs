@@ -0,0 +1,70 @@
+use fmp::strategy::{BuyAndHold, MACrossover, PeriodicRebalance, Strategy, MACD_ADX};
+use fmp::ta::MAType;
+use fmp::trade::{Backtester, MarketData};
+use peroxide::fuga::*;
+use std::collections::HashMap;
+use std::env::args;
+
+#[tokio::main]
+async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let symbol = args().nth(1).unwrap_or("005930.KS".to_string());
+    let symbols = vec![symbol.clone()];
+    let from = "2018-01-01 00:00:00 +09";
+    let to = "2023-10-10 00:00:00 +09";
+    let init_balance = 1000_0000f64;
+    let interest_rate = 0.04f64;
+    let sec_fee = 0.00015f64;
+
+    let weight = HashMap::from_iter(symbols.clone().into_iter().zip(vec![1f64]));
+    let strategies: Vec<Box<dyn Strategy>> = vec![
+        Box::new(BuyAndHold::new(
+            weight.clone(),
+            Box::new(PeriodicRebalance::new(usize::MAX)),
+        )),
+        Box::new(MACrossover::new(20, 50, MAType::SMA, weight.clone())),
+        Box::new(MACD_ADX::new(12, 26, 9, 14, 25f64, weight.clone())),
+    ];
+
+    let market_data = MarketData::new(&symbols, from, to).await?;
+
+    let mut dg = DataFrame::new(vec![]);
+    let mut names = vec![];
+    let mut cagr = vec![];
+    let mut volatility = vec![];
+    let mut sharpe = vec![];
+    let mut mdd = vec![];
+    for strategy in strategies {
+        let mut bt = Backtester::from_market_data(
+            &symbols,
+            init_balance,
+            strategy,
+            market_data.clone(),
+            interest_rate,
+            sec_fee,
+        );
+        let report = bt.run(120);
+        report.to_dataframe().write_parquet(
+            &format!("data/{}_{}.parquet", symbol, report.strategy),
+            CompressionOptions::Uncompressed,
+        )?;
+        names.push(report.strategy.clone());
+        cagr.push(report.get_cagr());
+        volatility.push(report.get_volatility());
+        sharpe.push(report.get_sharpe_ratio());
+        mdd.push(report.get_mdd());
+    }
+    dg.push("Strategy", Series::new(names));
+    dg.push("CAGR", Series::new(cagr));
+    dg.push("Volatility", Series::new(volatility));
+    dg.push("Sharpe", Series::new(sharpe));
+    dg.push("MDD", Series::new(mdd));
+
+    dg.print();
+
+    dg.write_parquet(
+        &format!("data/{}_summary.parquet", symbol),
+        CompressionOptions::Uncompressed,
+    )?;
+
+    Ok(())
+}
@@ -0,0 +1,160 @@
+use crate::batch::{Indicator, IndicatorTable};
+
+// ┌──────────────────────────────────────────────────────────┐
+//  Operand
+// └──────────────────────────────────────────────────────────┘
+/// Value to compare in a condition
+#[derive(Debug, Clone, Copy)]
+pub enum Operand {
+    Const(f64),
+    /// `k`-th output of an indicator (e.g. `MACD` has line, signal, histogram)
+    Output(Indicator, usize),
+}
+
+impl Operand {
+    /// First output of an indicator
+    pub fn of(indicator: Indicator) -> Self {
+        Operand::Output(indicator, 0)
+    }
+
+    pub fn close() -> Self {
+        Operand::of(Indicator::Close)
+    }
+
+    pub fn column(&self) -> Option<String> {
+        match self {
+            Operand::Const(_) => None,
+            Operand::Output(indicator, k) => indicator.columns().get(*k).cloned(),
+        }
+    }
+
+    /// Value at bar `i` (`NaN` if not available)
+    pub fn value(&self, table: &IndicatorTable, i: usize) -> f64 {
+        match self {
+            Operand::Const(x) => *x,
+            Operand::Output(..) => self
+                .column()
+                .and_then(|c| table.get(&c))
+                .and_then(|v| v.get(i).cloned())
+                .unwrap_or(f64::NAN),
+        }
+    }
+}
+
+// ┌──────────────────────────────────────────────────────────┐
+//  Condition
+// └──────────────────────────────────────────────────────────┘
+/// Composable condition over indicators
+///
+/// - Comparisons with `NaN` are always false
+/// - Crosses compare bar `i` with bar `i - 1`
+#[derive(Debug, Clone)]
+pub enum Condition {
+    Gt(Operand, Operand),
+    Lt(Operand, Operand),
+    CrossAbove(Operand, Operand),
+    CrossBelow(Operand, Operand),
+    And(Vec<Condition>),
+    Or(Vec<Condition>),
+    Not(Box<Condition>),
+}
+
+impl Condition {
+    pub fn gt(lhs: Operand, rhs: Operand) -> Self {
+        Condition::Gt(lhs, rhs)
+    }
+
+    pub fn lt(lhs: Operand, rhs: Operand) -> Self {
+        Condition::Lt(lhs, rhs)
+    }
+
+    pub fn cross_above(lhs: Operand, rhs: Operand) -> Self {
+        Condition::CrossAbove(lhs, rhs)
+    }
+
+    pub fn cross_below(lhs: Operand, rhs: Operand) -> Self {
+        Condition::CrossBelow(lhs, rhs)
+    }
+
+    pub fn and(self, other: Condition) -> Self {
+        match self {
+            Condition::And(mut v) => {
+                v.push(other);
+                Condition::And(v)
+            }
+            c => Condition::And(vec![c, other]),
+        }
+    }
+
+    pub fn or(self, other: Condition) -> Self {
+        match self {
+            Condition::Or(mut v) => {
+                v.push(other);
+                Condition::Or(v)
+            }
+            c => Condition::Or(vec![c, other]),
+        }
+    }
+
+    /// All operands which appear in the condition
+    pub fn operands(&self) -> Vec<Operand> {
+        match self {
+            Condition::Gt(a, b)
+            | Condition::Lt(a, b)
+            | Condition::CrossAbove(a, b)
+            | Condition::CrossBelow(a, b) => vec![*a, *b],
+            Condition::And(v) | Condition::Or(v) => v.iter().flat_map(|c| c.operands()).collect(),
+            Condition::Not(c) => c.operands(),
+        }
+    }
+
+    /// Indicators required to evaluate the condition (deduplicated)
+    pub fn indicators(&self) -> Vec<Indicator> {
+        indicators_of(&self.operands())
+    }
+
+    /// Evaluate at bar `i`
+    pub fn evaluate(&self, table: &IndicatorTable, i: usize) -> bool {
+        match self {
+            Condition::Gt(a, b) => a.value(table, i) > b.value(table, i),
+            Condition::Lt(a, b) => a.value(table, i) < b.value(table, i),
+            Condition::CrossAbove(a, b) => {
+                i > 0
+                    && a.value(table, i - 1) <= b.value(table, i - 1)
+                    && a.value(table, i) > b.value(table, i)
+            }
+            Condition::CrossBelow(a, b) => {
+                i > 0
+                    && a.value(table, i - 1) >= b.value(table, i - 1)
+                    && a.value(table, i) < b.value(table, i)
+            }
+            Condition::And(v) => v.iter().all(|c| c.evaluate(table, i)),
+            Condition::Or(v) => v.iter().any(|c| c.evaluate(table, i)),
+            Condition::Not(c) => !c.evaluate(table, i),
+        }
+    }
+}
+
+/// Indicators of the operands (deduplicated by output columns, in order of appearance)
+pub fn indicators_of(operands: &[Operand]) -> Vec<Indicator> {
+    let mut indicators: Vec<Indicator> = vec![];
+    for operand in operands {
+        if let Operand::Output(indicator, _) = *operand {
+            if !indicators
+                .iter()
+                .any(|x| x.columns() == indicator.columns())
+            {
+                indicators.push(indicator);
+            }
+        }
+    }
+    indicators
+}
+
+impl std::ops::Not for Condition {
+    type Output = Condition;
+
+    fn not(self) -> Self::Output {
+        Condition::Not(Box::new(self))
+    }
+}
@@ -0,0 +1,631 @@
+use crate::api::Timeframe;
+use crate::pairs::SIGNIFICANCE;
+use crate::portfolio::Allocation;
+use crate::strategy::{
+    AllocationStrategy, Band, BandRebalance, BuyAndHold, CalendarRebalance, CalendarTrigger,
+    MACrossover, Momentum, MomentumRule, PairsTrading, PeriodicRebalance, RebalanceStrategy,
+    ScheduledWeight, Strategy, ThresholdRebalance, VolatilityTarget, WeightSchedule, MACD_ADX,
+};
+use crate::ta::MAType;
+use crate::trade::{Backtester, MarketData, Portfolio};
+use serde::Deserialize;
+use std::collections::HashMap;
+use time::Weekday;
+
+/// Tolerance of the weight sum (e.g. `[0.2, 0.4, 0.3, 0.1]` sums to slightly more than 1)
+const EPS: f64 = 1e-9;
+
+// ┌──────────────────────────────────────────────────────────┐
+//  Backtest Config
+// └──────────────────────────────────────────────────────────┘
+/// Experiment definition loaded from a TOML or YAML file
+///
+/// - weights : allocation of each symbol (same order as `symbols`), the rest is cash
+/// - weights are not required by scheduled strategies (`piecewise`, `glide`) or `allocation`
+/// - rebalance : required only by strategies which rebalance (e.g. `buy_and_hold`)
+/// - momentum : every symbol of the rule (and `hurdle`) should be in `symbols`
+/// - pairs : `y` and `x` should be in `symbols` (short positions are allowed)
+/// - vol_target : wraps the inner `strategy`, both use the same `rebalance` section
+/// - borrow_rate : annual rate paid on a negative balance (defaults to `interest_rate`)
+#[derive(Debug, Clone, Deserialize)]
+pub struct BacktestConfig {
+    pub name: String,
+    pub symbols: Vec<String>,
+    #[serde(default)]
+    pub weights: Vec<f64>,
+    pub from: String,
+    pub to: String,
+    pub init_balance: f64,
+    #[serde(default)]
+    pub interest_rate: f64,
+    pub borrow_rate: Option<f64>,
+    #[serde(default)]
+    pub sec_fee: f64,
+    #[serde(default = "default_rolling_window")]
+    pub rolling_window: usize,
+    pub strategy: StrategyConfig,
+    pub rebalance: Option<RebalanceConfig>,
+}
+
+fn default_rolling_window() -> usize {
+    120
+}
+
+fn parse_weekday(weekday: &str) -> Result<Weekday, Box<dyn std::error::Error>> {
+    match weekday.to_lowercase().as_str() {
+        "monday" => Ok(Weekday::Monday),
+        "tuesday" => Ok(Weekday::Tuesday),
+        "wednesday" => Ok(Weekday::Wednesday),
+        "thursday" => Ok(Weekday::Thursday),
+        "friday" => Ok(Weekday::Friday),
+        "saturday" => Ok(Weekday::Saturday),
+        "sunday" => Ok(Weekday::Sunday),
+        _ => Err(format!("config: unknown weekday {}", weekday).into()),
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum StrategyConfig {
+    BuyAndHold {
+        #[serde(default)]
+        min_trade: f64,
+    },
+    MaCrossover {
+        short_period: usize,
+        long_period: usize,
+        ma_type: MAType,
+    },
+    MacdAdx {
+        fast_period: usize,
+        slow_period: usize,
+        signal_period: usize,
+        adx_period: usize,
+        adx_threshold: f64,
+    },
+    Piecewise {
+        steps: Vec<WeightStep>,
+        #[serde(default)]
+        min_trade: f64,
+    },
+    Glide {
+        from: String,
+        to: String,
+        start: Vec<f64>,
+        end: Vec<f64>,
+        #[serde(default)]
+        min_trade: f64,
+    },
+    Allocation {
+        allocation: Allocation,
+        lookback: usize,
+        #[serde(default)]
+        min_trade: f64,
+    },
+    VolTarget {
+        strategy: Box<StrategyConfig>,
+        target_volatility: f64,
+        window: usize,
+        #[serde(default = "default_max_leverage")]
+        max_leverage: f64,
+        #[serde(default)]
+        min_trade: f64,
+    },
+    Momentum {
+        rule: MomentumRule,
+        lookback: usize,
+        #[serde(default)]
+        skip: usize,
+        hurdle: Option<String>,
+        #[serde(default)]
+        min_trade: f64,
+    },
+    Pairs {
+        y: String,
+        x: String,
+        lookback: usize,
+        entry_z: f64,
+        #[serde(default)]
+        exit_z: f64,
+        stop_z: Option<f64>,
+        weight: f64,
+        significance: Option<f64>,
+        #[serde(default = "default_lags")]
+        lags: usize,
+    },
+}
+
+fn default_lags() -> usize {
+    1
+}
+
+fn default_max_leverage() -> f64 {
+    1f64
+}
+
+/// Weights which apply from `date` (YYYY-MM-DD)
+#[derive(Debug, Clone, Deserialize)]
+pub struct WeightStep {
+    pub date: String,
+    pub weights: Vec<f64>,
+}
+
+/// - threshold : percentage of allocation deviation
+/// - calendar : exactly one of `first` / `last` (timeframe) or `weekday` (e.g. "monday")
+/// - band : default `absolute` / `relative` band, per-symbol `bands` and `partial` rebalancing
+#[derive(Debug, Clone, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum RebalanceConfig {
+    Periodic {
+        period: usize,
+    },
+    Threshold {
+        threshold: f64,
+    },
+    Calendar {
+        first: Option<Timeframe>,
+        last: Option<Timeframe>,
+        weekday: Option<String>,
+    },
+    Band {
+        absolute: f64,
+        relative: f64,
+        #[serde(default)]
+        bands: HashMap<String, Band>,
+        #[serde(default)]
+        partial: bool,
+    },
+}
+
+impl BacktestConfig {
+    /// Load from `.toml`, `.yaml` or `.yml`
+    pub fn from_file(path: &str) -> Result<Self, Box<dyn std::error::Error>> {
+        let content = std::fs::read_to_string(path)?;
+        if path.ends_with(".toml") {
+            Self::from_toml(&content)
+        } else if path.ends_with(".yaml") || path.ends_with(".yml") {
+            Self::from_yaml(&content)
+        } else {
+            Err(format!("config: unknown file format of {}", path).into())
+        }
+    }
+
+    pub fn from_toml(content: &str) -> Result<Self, Box<dyn std::error::Error>> {
+        let config: Self = toml::from_str(content)?;
+        config.validate()?;
+        Ok(config)
+    }
+
+    pub fn from_yaml(content: &str) -> Result<Self, Box<dyn std::error::Error>> {
+        let config: Self = serde_yaml::from_str(content)?;
+        config.validate()?;
+        Ok(config)
+    }
+
+    fn validate(&self) -> Result<(), Box<dyn std::error::Error>> {
+        if self.symbols.is_empty() {
+            return Err("config: symbols should not be empty".into());
+        }
+        if !self.weights.is_empty() {
+            self.check_weight(&self.weights)?;
+        }
+        self.validate_strategy(&self.strategy)
+    }
+
+    fn validate_strategy(
+        &self,
+        strategy: &StrategyConfig,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        match strategy {
+            StrategyConfig::Piecewise { steps, .. } => {
+                for step in steps.iter() {
+                    self.check_weight(&step.weights)?;
+                }
+            }
+            StrategyConfig::Glide { start, end, .. } => {
+                self.check_weight(start)?;
+                self.check_weight(end)?;
+            }
+            StrategyConfig::Allocation { lookback, .. } if *lookback < 2 => {
+                return Err("config: allocation lookback should be at least 2".into());
+            }
+            StrategyConfig::VolTarget {
+                strategy,
+                target_volatility,
+                window,
+                max_leverage,
+                ..
+            } => {
+                if *target_volatility <= 0f64 || *max_leverage <= 0f64 {
+                    return Err(
+                        "config: target_volatility and max_leverage should be positive".into(),
+                    );
+                }
+                if *window < 2 {
+                    return Err("config: vol_target window should be at least 2".into());
+                }
+                self.validate_strategy(strategy)?;
+            }
+            StrategyConfig::Momentum {
+                rule,
+                lookback,
+                hurdle,
+                ..
+            } => {
+                if *lookback == 0 {
+                    return Err("config: momentum lookback should be positive".into());
+                }
+                let mut symbols = match rule {
+                    MomentumRule::CrossSectional { universe, .. } => universe.clone(),
+                    MomentumRule::Absolute { universe, fallback }
+                    | MomentumRule::Dual {
+                        universe, fallback, ..
+                    } => universe.iter().cloned().chain(fallback.clone()).collect(),
+                    MomentumRule::GEM { us, intl, bond } => {
+                        vec![us.clone(), intl.clone(), bond.clone()]
+                    }
+                };
+                symbols.extend(hurdle.clone());
+                if let Some(symbol) = symbols.iter().find(|s| !self.symbols.contains(s)) {
+                    return Err(
+                        format!("config: momentum symbol {} is not in symbols", symbol).into(),
+                    );
+                }
+            }
+            StrategyConfig::Pairs {
+                y,
+                x,
+                lookback,
+                entry_z,
+                exit_z,
+                significance,
+                ..
+            } => {
+                if let Some(symbol) = [y, x].into_iter().find(|s| !self.symbols.contains(s)) {
+                    return Err(format!("config: pairs symbol {} is not in symbols", symbol).into());
+                }
+                if *lookback < 3 || *entry_z <= *exit_z {
+                    return Err("config: pairs needs lookback >= 3 and entry_z > exit_z".into());
+                }
+                if let Some(significance) = significance {
+                    if !SIGNIFICANCE.contains(significance) {
+                        return Err(format!(
+                            "config: significance should be one of {:?}",
+                            SIGNIFICANCE
+                        )
+                        .into());
+                    }
+                }
+            }
+            _ => (),
+        }
+        Ok(())
+    }
+
+    fn check_weight(&self, weights: &[f64]) -> Result<(), Box<dyn std::error::Error>> {
+        if weights.len() != self.symbols.len() {
+            return Err(format!(
+                "config: {} weights for {} symbols",
+                weights.len(),
+                self.symbols.len()
+            )
+            .into());
+        }
+        if weights.iter().any(|&w| w < 0f64) || weights.iter().sum::<f64>() > 1f64 + EPS {
+            return Err("config: weights should be non-negative and sum to at most 1".into());
+        }
+        Ok(())
+    }
+
+    fn to_weight(&self, weights: &[f64]) -> HashMap<String, f64> {
+        self.symbols
+            .iter()
+            .cloned()
+            .zip(weights.iter().cloned())
+            .collect()
+    }
+
+    pub fn get_weight(&self) -> HashMap<String, f64> {
+        self.to_weight(&self.weights)
+    }
+
+    fn require_weight(&self) -> Result<HashMap<String, f64>, Box<dyn std::error::Error>> {
+        if self.weights.is_empty() {
+            return Err(format!("config: {} requires weights", self.name).into());
+        }
+        Ok(self.get_weight())
+    }
+
+    pub fn build_rebalance_strategy(
+        &self,
+    ) -> Result<Box<dyn RebalanceStrategy>, Box<dyn std::error::Error>> {
+        match &self.rebalance {
+            Some(RebalanceConfig::Periodic { period }) => {
+                if *period == 0 {
+                    return Err("config: rebalance period should be positive".into());
+                }
+                Ok(Box::new(PeriodicRebalance::new(*period)))
+            }
+            Some(RebalanceConfig::Threshold { threshold }) => Ok(Box::new(
+                ThresholdRebalance::new(*threshold, &self.require_weight()?),
+            )),
+            Some(RebalanceConfig::Calendar {
+                first,
+                last,
+                weekday,
+            }) => {
+                let trigger = match (first, last, weekday) {
+                    (Some(timeframe), None, None) => CalendarTrigger::First(*timeframe),
+                    (None, Some(timeframe), None) => CalendarTrigger::Last(*timeframe),
+                    (None, None, Some(weekday)) => {
+                        CalendarTrigger::Weekday(parse_weekday(weekday)?)
+                    }
+                    _ => {
+                        return Err(
+                            "config: calendar rebalance needs one of first, last or weekday".into(),
+                        )
+                    }
+                };
+                Ok(Box::new(CalendarRebalance::new(trigger)))
+            }
+            Some(RebalanceConfig::Band {
+                absolute,
+                relative,
+                bands,
+                partial,
+            }) => {
+                let mut rebalance = BandRebalance::new(
+                    &self.require_weight()?,
+                    Band::new(*absolute, *relative),
+                    *partial,
+                );
+                for (symbol, band) in bands.iter() {
+                    rebalance.set_band(symbol, *band);
+                }
+                Ok(Box::new(rebalance))
+            }
+            None => Err(format!("config: {} requires a rebalance section", self.name).into()),
+        }
+    }
+
+    pub fn build_strategy(&self) -> Result<Box<dyn Strategy>, Box<dyn std::error::Error>> {
+        self.strategy_from(&self.strategy)
+    }
+
+    fn strategy_from(
+        &self,
+        strategy: &StrategyConfig,
+    ) -> Result<Box<dyn Strategy>, Box<dyn std::error::Error>> {
+        let strategy: Box<dyn Strategy> = match strategy {
+            StrategyConfig::BuyAndHold { min_trade } => {
+                let mut bnh =
+                    BuyAndHold::new(self.require_weight()?, self.build_rebalance_strategy()?);
+                bnh.min_trade = *min_trade;
+                Box::new(bnh)
+            }
+            StrategyConfig::MaCrossover {
+                short_period,
+                long_period,
+                ma_type,
+            } => Box::new(MACrossover::new(
+                *short_period,
+                *long_period,
+                *ma_type,
+                self.require_weight()?,
+            )),
+            StrategyConfig::MacdAdx {
+                fast_period,
+                slow_period,
+                signal_period,
+                adx_period,
+                adx_threshold,
+            } => Box::new(MACD_ADX::new(
+                *fast_period,
+                *slow_period,
+                *signal_period,
+                *adx_period,
+                *adx_threshold,
+                self.require_weight()?,
+            )),
+            StrategyConfig::Piecewise { steps, min_trade } => {
+                let steps = steps
+                    .iter()
+                    .map(|step| (step.date.clone(), self.to_weight(&step.weights)))
+                    .collect();
+                let schedule = WeightSchedule::piecewise(steps)?;
+                let mut sw = ScheduledWeight::new(schedule, self.build_rebalance_strategy()?);
+                sw.min_trade = *min_trade;
+                Box::new(sw)
+            }
+            StrategyConfig::Glide {
+                from,
+                to,
+                start,
+                end,
+                min_trade,
+            } => {
+                let schedule =
+                    WeightSchedule::glide(from, to, self.to_weight(start), self.to_weight(end))?;
+                let mut sw = ScheduledWeight::new(schedule, self.build_rebalance_strategy()?);
+                sw.min_trade = *min_trade;
+                Box::new(sw)
+            }
+            StrategyConfig::Allocation {
+                allocation,
+                lookback,
+                min_trade,
+            } => {
+                let mut alloc = AllocationStrategy::new(
+                    &self.symbols,
+                    *allocation,
+                    *lookback,
+                    self.build_rebalance_strategy()?,
+                );
+                alloc.min_trade = *min_trade;
+                Box::new(alloc)
+            }
+            StrategyConfig::VolTarget {
+                strategy,
+                target_volatility,
+                window,
+                max_leverage,
+                min_trade,
+            } => {
+                let mut vt = VolatilityTarget::new(
+                    self.strategy_from(strategy)?,
+                    *target_volatility,
+                    *window,
+                    *max_leverage,
+                    self.build_rebalance_strategy()?,
+                );
+                vt.min_trade = *min_trade;
+                Box::new(vt)
+            }
+            StrategyConfig::Momentum {
+                rule,
+                lookback,
+                skip,
+                hurdle,
+                min_trade,
+            } => {
+                let mut momentum = Momentum::new(
+                    rule.clone(),
+                    *lookback,
+                    *skip,
+                    self.build_rebalance_strategy()?,
+                );
+                momentum.hurdle = hurdle.clone();
+                momentum.min_trade = *min_trade;
+                Box::new(momentum)
+            }
+            StrategyConfig::Pairs {
+                y,
+                x,
+                lookback,
+                entry_z,
+                exit_z,
+                stop_z,
+                weight,
+                significance,
+                lags,
+            } => {
+                let mut pairs = PairsTrading::new(y, x, *lookback, *entry_z, *exit_z, *weight);
+                pairs.stop_z = *stop_z;
+                pairs.significance = *significance;
+                pairs.lags = *lags;
+                Box::new(pairs)
+            }
+        };
+        Ok(strategy)
+    }
+
+    pub fn build_portfolio(&self) -> Portfolio {
+        Portfolio::new(self.init_balance, &self.symbols)
+    }
+
+    pub async fn build_market_data(&self) -> Result<MarketData, Box<dyn std::error::Error>> {
+        MarketData::new(&self.symbols, &self.from, &self.to).await
+    }
+
+    pub async fn build_backtester(&self) -> Result<Backtester, Box<dyn std::error::Error>> {
+        let mut backtester = Backtester::from_market_data(
+            &self.symbols,
+            self.init_balance,
+            self.build_strategy()?,
+            self.build_market_data().await?,
+            self.interest_rate,
+            self.sec_fee,
+        );
+        if let Some(borrow_rate) = self.borrow_rate {
+            backtester.borrow_rate = borrow_rate;
+        }
+        Ok(backtester)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const HEADER: &str = r#"
+name = "test"
+symbols = ["SPY", "TLT"]
+from = "2020-01-01 00:00:00 +09"
+to = "2021-01-01 00:00:00 +09"
+init_balance = 10000
+"#;
+
+    fn load(file: &str) -> BacktestConfig {
+        let path = format!("{}/config/{}", env!("CARGO_MANIFEST_DIR"), file);
+        let config = BacktestConfig::from_file(&path).unwrap();
+        config.build_strategy().unwrap();
+        config
+    }
+
+    fn error_of(body: &str) -> String {
+        BacktestConfig::from_toml(&format!("{}{}", HEADER, body))
+            .unwrap_err()
+            .to_string()
+    }
+
+    #[test]
+    fn shipped_configs_load() {
+        assert_eq!(load("gem.toml").name, "gem");
+        assert_eq!(load("glide_path.toml").name, "glide_path");
+        assert_eq!(load("pairs_samsung.toml").name, "pairs_samsung");
+        assert_eq!(load("risk_parity.toml").name, "risk_parity");
+        assert_eq!(load("trade_test_07.toml").name, "trade_test_07");
+        assert_eq!(load("vol_target.yaml").name, "vol_target");
+        assert_eq!(load("dual_momentum.yaml").name, "dual_momentum");
+        assert_eq!(load("ma_crossover.yaml").name, "ma_crossover");
+    }
+
+    #[test]
+    fn unknown_symbol_is_rejected() {
+        let body = r#"
+[strategy]
+type = "pairs"
+y = "SPY"
+x = "QQQ"
+lookback = 60
+entry_z = 2.0
+exit_z = 0.5
+weight = 1.0
+"#;
+        assert_eq!(error_of(body), "config: pairs symbol QQQ is not in symbols");
+    }
+
+    #[test]
+    fn weights_over_one_are_rejected() {
+        let body = r#"
+weights = [0.6, 0.5]
+
+[strategy]
+type = "buy_and_hold"
+"#;
+        assert_eq!(
+            error_of(body),
+            "config: weights should be non-negative and sum to at most 1"
+        );
+        let body = body.replace("0.6", "0.5");
+        assert!(BacktestConfig::from_toml(&format!("{}{}", HEADER, body)).is_ok());
+    }
+
+    #[test]
+    fn missing_fallback_is_rejected() {
+        let body = r#"
+[strategy]
+type = "momentum"
+lookback = 252
+
+[strategy.rule]
+type = "absolute"
+universe = ["SPY"]
+fallback = "AGG"
+"#;
+        assert_eq!(
+            error_of(body),
+            "config: momentum symbol AGG is not in symbols"
+        );
+    }
+}
@@ -1,4 +1,11 @@
 pub mod api;
+pub mod batch;
+pub mod condition;
+pub mod config;
+pub mod pairs;
+pub mod pattern;
+pub mod portfolio;
+pub mod screener;
 pub mod strategy;
 pub mod ta;
 pub mod trade;
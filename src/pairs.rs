@@ -0,0 +1,378 @@
+use crate::trade::MarketData;
+use peroxide::fuga::*;
+
+// ┌──────────────────────────────────────────────────────────┐
+//  Regression
+// └──────────────────────────────────────────────────────────┘
+/// Relative tolerance below which a regression is treated as singular
+const SINGULAR: f64 = 1e-12;
+
+/// OLS fit of `y = alpha + beta * x`
+///
+/// Return: (alpha, beta, residuals)
+pub fn hedge_ratio(
+    y: &[f64],
+    x: &[f64],
+) -> Result<(f64, f64, Vec<f64>), Box<dyn std::error::Error>> {
+    if y.len() != x.len() || y.len() < 3 {
+        return Err("hedge_ratio: at least 3 observations of the same length are required".into());
+    }
+    let (y, x) = (y.to_vec(), x.to_vec());
+    let (my, mx) = (y.mean(), x.mean());
+    let sxx = x.fmap(|t| (t - mx).powi(2)).sum();
+    if sxx <= SINGULAR * x.fmap(|t| t * t).sum() {
+        return Err("hedge_ratio: x should not be constant".into());
+    }
+    let sxy = zip_with(|a, b| (a - mx) * (b - my), &x, &y).sum();
+    let beta = sxy / sxx;
+    let alpha = my - beta * mx;
+    let residuals = zip_with(|a, b| b - alpha - beta * a, &x, &y);
+    Ok((alpha, beta, residuals))
+}
+
+/// z-score of the last value of a spread
+pub fn zscore(spread: &[f64]) -> f64 {
+    let spread = spread.to_vec();
+    let last = *spread.last().unwrap_or(&f64::NAN);
+    (last - spread.mean()) / spread.sd()
+}
+
+/// t-statistic of the `idx`-th coefficient of an OLS fit (rows of regressors)
+///
+/// - Error if the regressors are (nearly) collinear, e.g. a constant column besides the intercept
+fn t_statistic(
+    rows: &[Vec<f64>],
+    y: &[f64],
+    idx: usize,
+) -> Result<f64, Box<dyn std::error::Error>> {
+    let (n, k) = (rows.len(), rows[0].len());
+    if n <= k {
+        return Err("t_statistic: not enough observations".into());
+    }
+    let mut xtx = zeros(k, k);
+    let mut xty = vec![0f64; k];
+    for (row, &yt) in rows.iter().zip(y.iter()) {
+        for i in 0..k {
+            xty[i] += row[i] * yt;
+            for j in 0..k {
+                xtx[(i, j)] += row[i] * row[j];
+            }
+        }
+    }
+    // det(X'X) / Π diag(X'X) is 1 for orthogonal regressors and 0 for collinear ones
+    let ratio = xtx.det() / (0..k).map(|i| xtx[(i, i)]).product::<f64>();
+    if !ratio.is_finite() || ratio <= SINGULAR {
+        return Err("t_statistic: singular regression".into());
+    }
+    let inv = xtx.inv();
+    let coef = (0..k)
+        .map(|i| (0..k).map(|j| inv[(i, j)] * xty[j]).sum::<f64>())
+        .collect::<Vec<f64>>();
+    let rss = rows
+        .iter()
+        .zip(y.iter())
+        .map(|(row, &yt)| (yt - row.dot(&coef)).powi(2))
+        .sum::<f64>();
+    let se = (rss / (n - k) as f64 * inv[(idx, idx)]).sqrt();
+    if !se.is_finite() || se == 0f64 {
+        return Err("t_statistic: singular regression".into());
+    }
+    Ok(coef[idx] / se)
+}
+
+// ┌──────────────────────────────────────────────────────────┐
+//  Unit Root & Cointegration Tests
+// └──────────────────────────────────────────────────────────┘
+/// Significance levels of the critical values
+pub const SIGNIFICANCE: [f64; 3] = [0.01, 0.05, 0.1];
+
+/// MacKinnon (2010) response surfaces with a constant (1%, 5%, 10%)
+///
+/// - ADF : 1 variable
+/// - Engle-Granger : 2 variables
+const ADF_SURFACE: [[f64; 4]; 3] = [
+    [-3.43035, -6.5393, -16.786, -79.433],
+    [-2.86154, -2.8903, -4.234, -40.040],
+    [-2.56677, -1.5384, -2.809, 0f64],
+];
+const EG_SURFACE: [[f64; 4]; 3] = [
+    [-3.89644, -10.9519, -33.527, 0f64],
+    [-3.33613, -6.1101, -6.823, 0f64],
+    [-3.04445, -4.2412, -2.720, 0f64],
+];
+
+fn critical_values(surface: &[[f64; 4]; 3], nobs: usize) -> [f64; 3] {
+    let t = nobs as f64;
+    surface.map(|b| b[0] + b[1] / t + b[2] / t.powi(2) + b[3] / t.powi(3))
+}
+
+/// Result of a unit root test
+///
+/// - statistic : t-statistic of the lagged level
+/// - critical_values : at 1%, 5% & 10% (`SIGNIFICANCE`)
+#[derive(Debug, Clone, Copy)]
+pub struct UnitRootTest {
+    pub statistic: f64,
+    pub lags: usize,
+    pub nobs: usize,
+    pub critical_values: [f64; 3],
+}
+
+impl UnitRootTest {
+    /// Reject the unit root (i.e. stationary / cointegrated) at 0.01, 0.05 or 0.1
+    pub fn reject(&self, significance: f64) -> Result<bool, Box<dyn std::error::Error>> {
+        match SIGNIFICANCE.iter().position(|&s| s == significance) {
+            Some(i) => Ok(self.statistic < self.critical_values[i]),
+            None => Err(format!("reject: significance should be one of {:?}", SIGNIFICANCE).into()),
+        }
+    }
+}
+
+/// Regression of the Dickey-Fuller test
+///
+/// - `Δy[t] = (c +) γ y[t-1] + Σ δ[i] Δy[t-i]` (i = 1..=lags)
+///
+/// Return: (t-statistic of γ, nobs)
+fn dickey_fuller(
+    series: &[f64],
+    lags: usize,
+    constant: bool,
+) -> Result<(f64, usize), Box<dyn std::error::Error>> {
+    let diff = series.windows(2).map(|v| v[1] - v[0]).collect::<Vec<f64>>();
+    let nobs = diff.len().saturating_sub(lags);
+    if nobs < lags + 10 {
+        return Err(format!(
+            "adf: {} observations are too few for {} lags",
+            series.len(),
+            lags
+        )
+        .into());
+    }
+    let mut rows = vec![];
+    for t in lags..diff.len() {
+        let mut row = vec![series[t]];
+        row.extend((1..=lags).map(|i| diff[t - i]));
+        if constant {
+            row.push(1f64);
+        }
+        rows.push(row);
+    }
+    let statistic = t_statistic(&rows, &diff[lags..], 0)?;
+    Ok((statistic, nobs))
+}
+
+/// Augmented Dickey-Fuller test with a constant
+///
+/// - lags : number of lagged differences
+pub fn adf(series: &[f64], lags: usize) -> Result<UnitRootTest, Box<dyn std::error::Error>> {
+    let (statistic, nobs) = dickey_fuller(series, lags, true)?;
+    Ok(UnitRootTest {
+        statistic,
+        lags,
+        nobs,
+        critical_values: critical_values(&ADF_SURFACE, nobs),
+    })
+}
+
+/// Engle-Granger two-step cointegration test
+///
+/// - alpha, beta : `y = alpha + beta * x` (beta is the hedge ratio)
+/// - test : ADF test of the residuals (Engle-Granger critical values)
+#[derive(Debug, Clone, Copy)]
+pub struct EngleGranger {
+    pub alpha: f64,
+    pub beta: f64,
+    pub test: UnitRootTest,
+}
+
+/// Engle-Granger test of `y` on `x`
+///
+/// - lags : number of lagged differences of the ADF test
+/// - Critical values use `y.len() - 1` observations (as statsmodels `coint`)
+pub fn engle_granger(
+    y: &[f64],
+    x: &[f64],
+    lags: usize,
+) -> Result<EngleGranger, Box<dyn std::error::Error>> {
+    let (alpha, beta, residuals) = hedge_ratio(y, x)?;
+    let (statistic, nobs) = dickey_fuller(&residuals, lags, false)?;
+    Ok(EngleGranger {
+        alpha,
+        beta,
+        test: UnitRootTest {
+            statistic,
+            lags,
+            nobs,
+            critical_values: critical_values(&EG_SURFACE, y.len() - 1),
+        },
+    })
+}
+
+// ┌──────────────────────────────────────────────────────────┐
+//  Pairs from MarketData
+// └──────────────────────────────────────────────────────────┘
+/// adj_close of `symbol` over `lookback` rows ending before row `end`
+pub fn prices(
+    market_data: &MarketData,
+    symbol: &str,
+    end: usize,
+    lookback: usize,
+) -> Result<Vec<f64>, Box<dyn std::error::Error>> {
+    if end > market_data.len() || end < lookback {
+        return Err(format!(
+            "prices: {} rows before row {} are not available",
+            lookback, end
+        )
+        .into());
+    }
+    market_data.chart[end - lookback..end]
+        .iter()
+        .map(|chart| {
+            chart
+                .get(symbol)
+                .map(|c| c.adj_close)
+                .ok_or_else(|| format!("prices: unknown symbol {}", symbol).into())
+        })
+        .collect()
+}
+
+/// Engle-Granger test of a pair (`y` on `x`) over a window
+pub fn test_pair(
+    market_data: &MarketData,
+    y: &str,
+    x: &str,
+    end: usize,
+    lookback: usize,
+    lags: usize,
+) -> Result<EngleGranger, Box<dyn std::error::Error>> {
+    let py = prices(market_data, y, end, lookback)?;
+    let px = prices(market_data, x, end, lookback)?;
+    engle_granger(&py, &px, lags)
+}
+
+/// (y, x, Engle-Granger test) of a pair
+pub type PairTest = (String, String, EngleGranger);
+
+/// Test every pair of `symbols` (earlier symbol as `y`)
+///
+/// Return: (y, x, test) sorted by the test statistic (most cointegrated first)
+pub fn scan_pairs(
+    market_data: &MarketData,
+    symbols: &[String],
+    end: usize,
+    lookback: usize,
+    lags: usize,
+) -> Result<Vec<PairTest>, Box<dyn std::error::Error>> {
+    let mut result = vec![];
+    for (i, y) in symbols.iter().enumerate() {
+        for x in symbols.iter().skip(i + 1) {
+            let eg = test_pair(market_data, y, x, end, lookback, lags)?;
+            result.push((y.to_string(), x.to_string(), eg));
+        }
+    }
+    result.sort_by(|a, b| a.2.test.statistic.total_cmp(&b.2.test.statistic));
+    Ok(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const N: usize = 120;
+
+    /// Deterministic noises in [-0.5, 0.5)
+    fn noise(t: usize, a: usize, m: usize) -> f64 {
+        ((t * a) % m) as f64 / m as f64 - 0.5
+    }
+
+    /// (stationary AR(1) with phi 0.6, random walk, x, y = 2 + 1.5 x + AR(1))
+    fn series() -> (Vec<f64>, Vec<f64>, Vec<f64>, Vec<f64>) {
+        let mut ar = vec![0f64];
+        let mut walk = vec![10f64];
+        let mut x = vec![10f64];
+        for t in 1..N {
+            ar.push(0.6 * ar[t - 1] + noise(t, 7919, 101));
+            walk.push(walk[t - 1] + noise(t, 7919, 101));
+            x.push(x[t - 1] + noise(t, 104729, 97));
+        }
+        let y = (0..N).map(|t| 2f64 + 1.5 * x[t] + ar[t]).collect();
+        (ar, walk, x, y)
+    }
+
+    // Expected values are hand-derived: the regressions of statsmodels
+    // `adfuller(maxlag=k, autolag=None)` and `coint(trend="c", maxlag=k, autolag=None)`,
+    // solved exactly in rationals on the same series.
+
+    fn assert_close(actual: &[f64], expected: &[f64], tol: f64) {
+        assert_eq!(actual.len(), expected.len());
+        for (a, e) in actual.iter().zip(expected.iter()) {
+            assert!((a - e).abs() < tol, "{:?} != {:?}", actual, expected);
+        }
+    }
+
+    #[test]
+    fn adf_matches_reference() {
+        let (ar, walk, _, _) = series();
+        let test = adf(&ar, 0).unwrap();
+        assert_eq!(test.nobs, 119);
+        assert_close(&[test.statistic], &[-10.381020352066999], 1e-8);
+        assert_close(
+            &test.critical_values,
+            &[-3.4865346059036564, -2.886150985847627, -2.5798960927900576],
+            1e-12,
+        );
+        assert!(test.reject(0.01).unwrap());
+
+        let test = adf(&ar, 2).unwrap();
+        assert_eq!(test.nobs, 117);
+        assert_close(&[test.statistic], &[-5.920289604816944], 1e-8);
+        assert_close(
+            &test.critical_values,
+            &[-3.487517288664615, -2.8865777180380032, -2.5801239192052012],
+            1e-12,
+        );
+
+        let test = adf(&walk, 2).unwrap();
+        assert_close(&[test.statistic], &[-2.2097722062577554], 1e-8);
+        assert!(!test.reject(0.1).unwrap());
+    }
+
+    #[test]
+    fn engle_granger_matches_reference() {
+        let (_, _, x, y) = series();
+        let eg = engle_granger(&y, &x, 0).unwrap();
+        assert_close(
+            &[eg.alpha, eg.beta],
+            &[1.696655290124732, 1.5298205842740324],
+            1e-10,
+        );
+        assert_close(&[eg.test.statistic], &[-10.44945181656147], 1e-8);
+        assert_close(
+            &eg.test.critical_values,
+            &[-3.990840331897465, -3.3879571944071745, -3.080282412965186],
+            1e-12,
+        );
+
+        let eg = engle_granger(&y, &x, 2).unwrap();
+        assert_eq!(eg.test.nobs, 117);
+        assert_close(&[eg.test.statistic], &[-5.956402975394888], 1e-8);
+        assert!(eg.test.reject(0.01).unwrap());
+    }
+
+    #[test]
+    fn singular_regressions_are_errors() {
+        let constant = vec![100.1; N];
+        let (_, _, x, y) = series();
+        assert!(hedge_ratio(&x, &constant).is_err());
+        assert!(adf(&constant, 1).is_err());
+        assert!(engle_granger(&x, &constant, 1).is_err());
+
+        // Second regressor is twice the first up to a tiny perturbation
+        let rows = x
+            .iter()
+            .enumerate()
+            .map(|(t, &v)| vec![v, 2f64 * v + 1e-6 * noise(t, 7919, 101), 1f64])
+            .collect::<Vec<_>>();
+        assert!(t_statistic(&rows, &y, 0).is_err());
+    }
+}
@@ -0,0 +1,288 @@
+use crate::api::{Chart, HistoricalChart};
+
+/// Number of bars used to decide the preceding trend of a pattern
+const TREND_PERIOD: usize = 5;
+
+// ┌──────────────────────────────────────────────────────────┐
+//  Candlestick Pattern
+// └──────────────────────────────────────────────────────────┘
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Direction {
+    Bullish,
+    Bearish,
+    Neutral,
+}
+
+impl Direction {
+    /// 1 for bullish, -1 for bearish, 0 for neutral
+    pub fn sign(&self) -> f64 {
+        match self {
+            Direction::Bullish => 1f64,
+            Direction::Bearish => -1f64,
+            Direction::Neutral => 0f64,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Pattern {
+    // Single bar
+    Doji,
+    Hammer,
+    HangingMan,
+    InvertedHammer,
+    ShootingStar,
+    // Two bars
+    BullishEngulfing,
+    BearishEngulfing,
+    BullishHarami,
+    BearishHarami,
+    // Three bars
+    MorningStar,
+    EveningStar,
+    ThreeWhiteSoldiers,
+    ThreeBlackCrows,
+}
+
+impl Pattern {
+    pub const ALL: [Pattern; 13] = [
+        Pattern::Doji,
+        Pattern::Hammer,
+        Pattern::HangingMan,
+        Pattern::InvertedHammer,
+        Pattern::ShootingStar,
+        Pattern::BullishEngulfing,
+        Pattern::BearishEngulfing,
+        Pattern::BullishHarami,
+        Pattern::BearishHarami,
+        Pattern::MorningStar,
+        Pattern::EveningStar,
+        Pattern::ThreeWhiteSoldiers,
+        Pattern::ThreeBlackCrows,
+    ];
+
+    pub fn direction(&self) -> Direction {
+        match self {
+            Pattern::Doji => Direction::Neutral,
+            Pattern::Hammer
+            | Pattern::InvertedHammer
+            | Pattern::BullishEngulfing
+            | Pattern::BullishHarami
+            | Pattern::MorningStar
+            | Pattern::ThreeWhiteSoldiers => Direction::Bullish,
+            Pattern::HangingMan
+            | Pattern::ShootingStar
+            | Pattern::BearishEngulfing
+            | Pattern::BearishHarami
+            | Pattern::EveningStar
+            | Pattern::ThreeBlackCrows => Direction::Bearish,
+        }
+    }
+
+    /// Number of bars which form the pattern (the last one is the signal bar)
+    pub fn bars(&self) -> usize {
+        match self {
+            Pattern::Doji
+            | Pattern::Hammer
+            | Pattern::HangingMan
+            | Pattern::InvertedHammer
+            | Pattern::ShootingStar => 1,
+            Pattern::BullishEngulfing
+            | Pattern::BearishEngulfing
+            | Pattern::BullishHarami
+            | Pattern::BearishHarami => 2,
+            Pattern::MorningStar
+            | Pattern::EveningStar
+            | Pattern::ThreeWhiteSoldiers
+            | Pattern::ThreeBlackCrows => 3,
+        }
+    }
+}
+
+/// Detected pattern at the signal bar `index`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PatternSignal {
+    pub index: usize,
+    pub pattern: Pattern,
+    pub direction: Direction,
+}
+
+/// Per-bar detection of a single pattern
+pub fn detect(hist: &HistoricalChart, pattern: Pattern) -> Vec<bool> {
+    let charts = hist.get_charts();
+    (0..charts.len())
+        .map(|i| is_pattern(charts, i, pattern))
+        .collect::<Vec<bool>>()
+}
+
+/// All detected patterns in chronological order
+pub fn scan(hist: &HistoricalChart) -> Vec<PatternSignal> {
+    let charts = hist.get_charts();
+    let mut signals = vec![];
+    for i in 0..charts.len() {
+        for pattern in Pattern::ALL.iter() {
+            if is_pattern(charts, i, *pattern) {
+                signals.push(PatternSignal {
+                    index: i,
+                    pattern: *pattern,
+                    direction: pattern.direction(),
+                });
+            }
+        }
+    }
+    signals
+}
+
+/// Net direction of all patterns on each bar
+///
+/// - 1 if bullish patterns dominate, -1 if bearish patterns dominate, 0 otherwise
+pub fn pattern_signal(hist: &HistoricalChart) -> Vec<f64> {
+    let mut net = vec![0f64; hist.get_charts().len()];
+    for signal in scan(hist) {
+        net[signal.index] += signal.direction.sign();
+    }
+    net.iter()
+        .map(|&x| {
+            if x > 0f64 {
+                1f64
+            } else if x < 0f64 {
+                -1f64
+            } else {
+                0f64
+            }
+        })
+        .collect::<Vec<f64>>()
+}
+
+// ┌──────────────────────────────────────────────────────────┐
+//  Candle geometry
+// └──────────────────────────────────────────────────────────┘
+fn body(c: &Chart) -> f64 {
+    (c.close - c.open).abs()
+}
+
+fn range(c: &Chart) -> f64 {
+    c.high - c.low
+}
+
+fn upper_shadow(c: &Chart) -> f64 {
+    c.high - c.open.max(c.close)
+}
+
+fn lower_shadow(c: &Chart) -> f64 {
+    c.open.min(c.close) - c.low
+}
+
+fn is_bullish(c: &Chart) -> bool {
+    c.close > c.open
+}
+
+fn is_bearish(c: &Chart) -> bool {
+    c.close < c.open
+}
+
+fn is_long(c: &Chart) -> bool {
+    body(c) >= 0.5 * range(c) && range(c) > 0f64
+}
+
+fn is_small(c: &Chart, reference: &Chart) -> bool {
+    body(c) <= 0.3 * body(reference)
+}
+
+/// Close change over the `TREND_PERIOD` bars before `i`
+fn prior_trend(charts: &[Chart], i: usize) -> f64 {
+    if i == 0 {
+        return 0f64;
+    }
+    charts[i - 1].close - charts[(i - 1).saturating_sub(TREND_PERIOD)].close
+}
+
+fn is_hammer_shape(c: &Chart) -> bool {
+    range(c) > 0f64 && lower_shadow(c) >= 2f64 * body(c) && upper_shadow(c) <= 0.1 * range(c)
+}
+
+fn is_inverted_hammer_shape(c: &Chart) -> bool {
+    range(c) > 0f64 && upper_shadow(c) >= 2f64 * body(c) && lower_shadow(c) <= 0.1 * range(c)
+}
+
+fn is_pattern(charts: &[Chart], i: usize, pattern: Pattern) -> bool {
+    if i + 1 < pattern.bars() {
+        return false;
+    }
+    let c = &charts[i];
+    match pattern {
+        Pattern::Doji => range(c) > 0f64 && body(c) <= 0.1 * range(c),
+        Pattern::Hammer => is_hammer_shape(c) && prior_trend(charts, i) < 0f64,
+        Pattern::HangingMan => is_hammer_shape(c) && prior_trend(charts, i) > 0f64,
+        Pattern::InvertedHammer => is_inverted_hammer_shape(c) && prior_trend(charts, i) < 0f64,
+        Pattern::ShootingStar => is_inverted_hammer_shape(c) && prior_trend(charts, i) > 0f64,
+        Pattern::BullishEngulfing => {
+            let p = &charts[i - 1];
+            is_bearish(p)
+                && is_bullish(c)
+                && c.open <= p.close
+                && c.close >= p.open
+                && body(c) > body(p)
+        }
+        Pattern::BearishEngulfing => {
+            let p = &charts[i - 1];
+            is_bullish(p)
+                && is_bearish(c)
+                && c.open >= p.close
+                && c.close <= p.open
+                && body(c) > body(p)
+        }
+        Pattern::BullishHarami => {
+            let p = &charts[i - 1];
+            is_bearish(p)
+                && is_long(p)
+                && is_bullish(c)
+                && c.open >= p.close
+                && c.close <= p.open
+                && body(c) < body(p)
+        }
+        Pattern::BearishHarami => {
+            let p = &charts[i - 1];
+            is_bullish(p)
+                && is_long(p)
+                && is_bearish(c)
+                && c.open <= p.close
+                && c.close >= p.open
+                && body(c) < body(p)
+        }
+        Pattern::MorningStar => {
+            let (first, star) = (&charts[i - 2], &charts[i - 1]);
+            is_bearish(first)
+                && is_long(first)
+                && is_small(star, first)
+                && star.open.max(star.close) <= first.close
+                && is_bullish(c)
+                && c.close > (first.open + first.close) / 2f64
+        }
+        Pattern::EveningStar => {
+            let (first, star) = (&charts[i - 2], &charts[i - 1]);
+            is_bullish(first)
+                && is_long(first)
+                && is_small(star, first)
+                && star.open.min(star.close) >= first.close
+                && is_bearish(c)
+                && c.close < (first.open + first.close) / 2f64
+        }
+        Pattern::ThreeWhiteSoldiers => (i - 2..=i).all(|j| {
+            let c = &charts[j];
+            let advancing = j == i - 2 || {
+                let p = &charts[j - 1];
+                c.open >= p.open && c.open <= p.close && c.close > p.close
+            };
+            is_bullish(c) && is_long(c) && upper_shadow(c) <= 0.3 * body(c) && advancing
+        }),
+        Pattern::ThreeBlackCrows => (i - 2..=i).all(|j| {
+            let c = &charts[j];
+            let declining = j == i - 2 || {
+                let p = &charts[j - 1];
+                c.open <= p.open && c.open >= p.close && c.close < p.close
+            };
+            is_bearish(c) && is_long(c) && lower_shadow(c) <= 0.3 * body(c) && declining
+        }),
+    }
+}
@@ -0,0 +1,621 @@
+use crate::trade::MarketData;
+use peroxide::fuga::*;
+use serde::Deserialize;
+use std::collections::HashMap;
+
+// ┌──────────────────────────────────────────────────────────┐
+//  Estimation
+// └──────────────────────────────────────────────────────────┘
+/// Daily returns of `symbols` over `lookback` rows ending before row `end`
+///
+/// Return: Matrix (lookback x symbols)
+pub fn returns(
+    market_data: &MarketData,
+    symbols: &[String],
+    end: usize,
+    lookback: usize,
+) -> Result<Matrix, Box<dyn std::error::Error>> {
+    if lookback < 2 || end > market_data.len() || end < lookback + 1 {
+        return Err(format!(
+            "returns: {} rows of returns before row {} are not available",
+            lookback, end
+        )
+        .into());
+    }
+    let mut data = vec![];
+    for t in end - lookback..end {
+        let prev = &market_data.chart[t - 1];
+        let curr = &market_data.chart[t];
+        for symbol in symbols.iter() {
+            let p0 = prev.get(symbol).ok_or("returns: unknown symbol")?.adj_close;
+            let p1 = curr.get(symbol).ok_or("returns: unknown symbol")?.adj_close;
+            data.push(p1 / p0 - 1f64);
+        }
+    }
+    Ok(matrix(data, lookback, symbols.len(), Row))
+}
+
+/// Annualized expected returns & covariance matrix from a rolling window
+///
+/// Return: (mu, cov)
+pub fn estimate(
+    market_data: &MarketData,
+    symbols: &[String],
+    end: usize,
+    lookback: usize,
+) -> Result<(Vec<f64>, Matrix), Box<dyn std::error::Error>> {
+    let r = returns(market_data, symbols, end, lookback)?;
+    Ok(annualize(&r))
+}
+
+/// Daily returns from price series (one series per symbol, same length)
+///
+/// Return: Matrix (prices - 1 x symbols)
+pub fn price_returns(prices: &[Vec<f64>]) -> Result<Matrix, Box<dyn std::error::Error>> {
+    let len = prices.first().map(|p| p.len()).unwrap_or(0);
+    if len < 3 || prices.iter().any(|p| p.len() != len) {
+        return Err("price_returns: at least 3 prices of the same length are required".into());
+    }
+    let mut data = vec![];
+    for t in 1..len {
+        for p in prices.iter() {
+            data.push(p[t] / p[t - 1] - 1f64);
+        }
+    }
+    Ok(matrix(data, len - 1, prices.len(), Row))
+}
+
+/// Annualized mean & covariance of daily returns
+///
+/// Return: (mu, cov)
+pub fn annualize(returns: &Matrix) -> (Vec<f64>, Matrix) {
+    let mu = returns.mean().fmap(|x| x * 252f64);
+    let mut cov = returns.cov();
+    for i in 0..cov.row {
+        for j in 0..cov.col {
+            cov[(i, j)] *= 252f64;
+        }
+    }
+    (mu, cov)
+}
+
+// ┌──────────────────────────────────────────────────────────┐
+//  Optimization
+// └──────────────────────────────────────────────────────────┘
+/// Objective of the portfolio optimization
+///
+/// - MinVariance : minimum variance portfolio
+/// - MaxSharpe(risk_free) : tangency portfolio for an annual risk-free rate
+/// - TargetReturn(target) : minimum variance with at least `target` annual return
+/// - MeanVariance(risk_aversion) : maximize `mu'w - risk_aversion / 2 * w'Σw`
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Objective {
+    MinVariance,
+    MaxSharpe(f64),
+    TargetReturn(f64),
+    MeanVariance(f64),
+}
+
+/// Bounds of each weight (weights always sum to 1)
+///
+/// - long_only : `[0, 1]`
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize)]
+pub struct WeightBounds {
+    pub lower: f64,
+    pub upper: f64,
+}
+
+impl WeightBounds {
+    pub fn new(lower: f64, upper: f64) -> Self {
+        Self { lower, upper }
+    }
+
+    pub fn long_only() -> Self {
+        Self::new(0f64, 1f64)
+    }
+}
+
+/// Optimal weights for the objective under the weight bounds
+///
+/// - mu : annualized expected returns
+/// - cov : annualized covariance matrix
+pub fn optimize(
+    mu: &[f64],
+    cov: &Matrix,
+    objective: Objective,
+    bounds: WeightBounds,
+) -> Result<Vec<f64>, Box<dyn std::error::Error>> {
+    let n = mu.len();
+    if n == 0 || cov.row != n || cov.col != n {
+        return Err("optimize: mu and cov should have the same (non-zero) size".into());
+    }
+    if mu.iter().chain(cov.data.iter()).any(|x| !x.is_finite()) {
+        return Err("optimize: mu and cov should be finite".into());
+    }
+    if bounds.lower > bounds.upper
+        || bounds.lower * n as f64 > 1f64
+        || bounds.upper * (n as f64) < 1f64
+    {
+        return Err("optimize: weight bounds are infeasible".into());
+    }
+
+    let ret = |w: &Vec<f64>| zip_with(|x, y| x * y, mu, w).sum();
+    match objective {
+        Objective::MinVariance => Ok(mean_variance(mu, cov, 0f64, bounds)),
+        Objective::MeanVariance(risk_aversion) => {
+            if risk_aversion <= 0f64 {
+                return Err("optimize: risk aversion should be positive".into());
+            }
+            // max mu'w - γ/2 w'Σw  <=>  min w'Σw - (2/γ) mu'w
+            Ok(mean_variance(mu, cov, 2f64 / risk_aversion, bounds))
+        }
+        Objective::TargetReturn(target) => {
+            if target > max_return(mu, bounds) + 1e-12 {
+                return Err(format!("optimize: target return {} is not attainable", target).into());
+            }
+            let w = mean_variance(mu, cov, 0f64, bounds);
+            if ret(&w) >= target {
+                return Ok(w);
+            }
+            // Bisection on the return tilt (return increases with the tilt)
+            let mut lo = 0f64;
+            let mut hi = 1f64;
+            let mut w_hi = mean_variance(mu, cov, hi, bounds);
+            while ret(&w_hi) < target && hi < 1e12 {
+                lo = hi;
+                hi *= 2f64;
+                w_hi = mean_variance(mu, cov, hi, bounds);
+            }
+            for _ in 0..60 {
+                let mid = 0.5 * (lo + hi);
+                let w_mid = mean_variance(mu, cov, mid, bounds);
+                if ret(&w_mid) >= target {
+                    hi = mid;
+                    w_hi = w_mid;
+                } else {
+                    lo = mid;
+                }
+            }
+            Ok(w_hi)
+        }
+        Objective::MaxSharpe(risk_free) => {
+            // Golden section search on the efficient frontier (log-scaled tilt)
+            let scale = cov.diag().mean() / mu.iter().fold(1e-12f64, |m, x| m.max(x.abs()));
+            let sharpe = |x: f64| {
+                let w = mean_variance(mu, cov, scale * 10f64.powf(x), bounds);
+                let s = (ret(&w) - risk_free) / quad_form(cov, &w).sqrt();
+                (s, w)
+            };
+            let gr = (5f64.sqrt() - 1f64) / 2f64;
+            let (mut a, mut b) = (-4f64, 4f64);
+            let mut c = b - gr * (b - a);
+            let mut d = a + gr * (b - a);
+            let (mut fc, mut wc) = sharpe(c);
+            let (mut fd, mut wd) = sharpe(d);
+            let mut best = if fc >= fd {
+                (fc, wc.clone())
+            } else {
+                (fd, wd.clone())
+            };
+            for _ in 0..40 {
+                if fc >= fd {
+                    b = d;
+                    d = c;
+                    (fd, wd) = (fc, wc);
+                    c = b - gr * (b - a);
+                    (fc, wc) = sharpe(c);
+                } else {
+                    a = c;
+                    c = d;
+                    (fc, wc) = (fd, wd);
+                    d = a + gr * (b - a);
+                    (fd, wd) = sharpe(d);
+                }
+                if fc > best.0 {
+                    best = (fc, wc.clone());
+                }
+                if fd > best.0 {
+                    best = (fd, wd.clone());
+                }
+            }
+            Ok(best.1)
+        }
+    }
+}
+
+/// Estimate from `MarketData` and optimize, keyed by symbol for the rebalancing strategies
+pub fn optimize_weight(
+    market_data: &MarketData,
+    symbols: &[String],
+    end: usize,
+    lookback: usize,
+    objective: Objective,
+    bounds: WeightBounds,
+) -> Result<HashMap<String, f64>, Box<dyn std::error::Error>> {
+    let (mu, cov) = estimate(market_data, symbols, end, lookback)?;
+    let w = optimize(&mu, &cov, objective, bounds)?;
+    Ok(symbols.iter().cloned().zip(w).collect())
+}
+
+/// Annualized (expected return, volatility) of the weights
+pub fn portfolio_performance(mu: &[f64], cov: &Matrix, w: &[f64]) -> (f64, f64) {
+    let ret = zip_with(|x, y| x * y, mu, w).sum();
+    (ret, quad_form(cov, w).sqrt())
+}
+
+// ┌──────────────────────────────────────────────────────────┐
+//  Risk-based Allocation
+// └──────────────────────────────────────────────────────────┘
+/// Inverse volatility weights
+pub fn inverse_volatility(cov: &Matrix) -> Vec<f64> {
+    let inv_vol = cov.diag().fmap(|x| 1f64 / x.sqrt());
+    let total = inv_vol.sum();
+    inv_vol.fmap(|x| x / total)
+}
+
+/// Risk budgeting weights (each asset contributes `budget[i]` of the portfolio risk)
+///
+/// - Cyclical coordinate descent on `y'Σy / 2 - Σ budget[i] ln(y[i])`
+pub fn risk_budget(cov: &Matrix, budget: &[f64]) -> Result<Vec<f64>, Box<dyn std::error::Error>> {
+    let n = budget.len();
+    if n == 0 || cov.row != n || cov.col != n {
+        return Err("risk_budget: budget and cov should have the same (non-zero) size".into());
+    }
+    if budget.iter().any(|&b| b <= 0f64) || cov.diag().iter().any(|&v| v <= 0f64) {
+        return Err("risk_budget: budgets and variances should be positive".into());
+    }
+    let total = budget.iter().sum::<f64>();
+    let budget = budget.iter().map(|b| b / total).collect::<Vec<f64>>();
+    let mut y = inverse_volatility(cov);
+    for _ in 0..1000 {
+        let mut diff = 0f64;
+        for i in 0..n {
+            let c = (0..n)
+                .filter(|&j| j != i)
+                .map(|j| cov[(i, j)] * y[j])
+                .sum::<f64>();
+            let v = cov[(i, i)];
+            let y_new = (-c + (c * c + 4f64 * v * budget[i]).sqrt()) / (2f64 * v);
+            diff = diff.max((y_new - y[i]).abs());
+            y[i] = y_new;
+        }
+        if diff < 1e-12 {
+            break;
+        }
+    }
+    let total = y.sum();
+    Ok(y.fmap(|x| x / total))
+}
+
+/// Equal risk contribution (risk parity) weights
+pub fn risk_parity(cov: &Matrix) -> Result<Vec<f64>, Box<dyn std::error::Error>> {
+    risk_budget(cov, &vec![1f64; cov.row])
+}
+
+/// Share of the portfolio risk contributed by each asset
+pub fn risk_contribution(cov: &Matrix, w: &[f64]) -> Vec<f64> {
+    let marginal = mat_vec(cov, w);
+    let total = quad_form(cov, w);
+    zip_with(|x, y| x * y / total, w, &marginal)
+}
+
+/// Hierarchical Risk Parity weights (López de Prado)
+///
+/// 1. Single-linkage clustering on the correlation distance `sqrt((1 - ρ) / 2)`
+/// 2. Quasi-diagonalization by the order of the dendrogram leaves
+/// 3. Recursive bisection with inverse-variance cluster variances
+pub fn hrp(cov: &Matrix) -> Result<Vec<f64>, Box<dyn std::error::Error>> {
+    let n = cov.row;
+    if n == 0 || cov.col != n {
+        return Err("hrp: cov should be a non-empty square matrix".into());
+    }
+    if cov.diag().iter().any(|&v| v <= 0f64) {
+        return Err("hrp: variances should be positive".into());
+    }
+    let dist = |i: usize, j: usize| {
+        let rho = cov[(i, j)] / (cov[(i, i)] * cov[(j, j)]).sqrt();
+        ((1f64 - rho) / 2f64).max(0f64).sqrt()
+    };
+
+    // Merging concatenates the leaves, so the last cluster is in dendrogram order
+    let mut clusters = (0..n).map(|i| vec![i]).collect::<Vec<Vec<usize>>>();
+    while clusters.len() > 1 {
+        let mut closest = (0, 1, f64::MAX);
+        for a in 0..clusters.len() {
+            for b in a + 1..clusters.len() {
+                let d = clusters[a]
+                    .iter()
+                    .flat_map(|&i| clusters[b].iter().map(move |&j| (i, j)))
+                    .map(|(i, j)| dist(i, j))
+                    .fold(f64::MAX, f64::min);
+                if d < closest.2 {
+                    closest = (a, b, d);
+                }
+            }
+        }
+        let right = clusters.remove(closest.1);
+        clusters[closest.0].extend(right);
+    }
+    let order = clusters.pop().unwrap_or_default();
+
+    let cluster_var = |items: &[usize]| {
+        let inv_var = items
+            .iter()
+            .map(|&i| 1f64 / cov[(i, i)])
+            .collect::<Vec<f64>>();
+        let total = inv_var.sum();
+        let w = inv_var.fmap(|x| x / total);
+        let mut var = 0f64;
+        for (a, &i) in items.iter().enumerate() {
+            for (b, &j) in items.iter().enumerate() {
+                var += w[a] * w[b] * cov[(i, j)];
+            }
+        }
+        var
+    };
+
+    let mut weight = vec![1f64; n];
+    let mut stack = vec![order];
+    while let Some(items) = stack.pop() {
+        if items.len() < 2 {
+            continue;
+        }
+        let (left, right) = items.split_at(items.len() / 2);
+        let (var_l, var_r) = (cluster_var(left), cluster_var(right));
+        let alpha = 1f64 - var_l / (var_l + var_r);
+        for &i in left {
+            weight[i] *= alpha;
+        }
+        for &i in right {
+            weight[i] *= 1f64 - alpha;
+        }
+        stack.push(left.to_vec());
+        stack.push(right.to_vec());
+    }
+    Ok(weight)
+}
+
+/// Weighting scheme recomputed from a window of returns
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Allocation {
+    EqualWeight,
+    InverseVolatility,
+    RiskParity,
+    #[serde(rename = "hrp")]
+    HRP,
+    Optimize(Objective, WeightBounds),
+}
+
+impl Allocation {
+    /// Weights from daily returns (time x symbols)
+    pub fn allocate(&self, returns: &Matrix) -> Result<Vec<f64>, Box<dyn std::error::Error>> {
+        let (mu, cov) = annualize(returns);
+        match self {
+            Allocation::EqualWeight => Ok(vec![1f64 / mu.len() as f64; mu.len()]),
+            Allocation::InverseVolatility => Ok(inverse_volatility(&cov)),
+            Allocation::RiskParity => risk_parity(&cov),
+            Allocation::HRP => hrp(&cov),
+            Allocation::Optimize(objective, bounds) => optimize(&mu, &cov, *objective, *bounds),
+        }
+    }
+}
+
+/// Allocate from a `MarketData` window, keyed by symbol for the rebalancing strategies
+pub fn allocate_weight(
+    market_data: &MarketData,
+    symbols: &[String],
+    end: usize,
+    lookback: usize,
+    allocation: Allocation,
+) -> Result<HashMap<String, f64>, Box<dyn std::error::Error>> {
+    let r = returns(market_data, symbols, end, lookback)?;
+    let w = allocation.allocate(&r)?;
+    Ok(symbols.iter().cloned().zip(w).collect())
+}
+
+fn mat_vec(m: &Matrix, v: &[f64]) -> Vec<f64> {
+    (0..m.row)
+        .map(|i| (0..m.col).map(|j| m[(i, j)] * v[j]).sum())
+        .collect()
+}
+
+fn quad_form(m: &Matrix, v: &[f64]) -> f64 {
+    zip_with(|x, y| x * y, &mat_vec(m, v), v).sum()
+}
+
+/// Largest return attainable under the bounds (fill the best assets first)
+fn max_return(mu: &[f64], bounds: WeightBounds) -> f64 {
+    let mut idx = (0..mu.len()).collect::<Vec<usize>>();
+    idx.sort_by(|&i, &j| mu[j].total_cmp(&mu[i]));
+    let mut w = vec![bounds.lower; mu.len()];
+    let mut left = 1f64 - bounds.lower * mu.len() as f64;
+    for i in idx {
+        let add = left.min(bounds.upper - bounds.lower);
+        w[i] += add;
+        left -= add;
+    }
+    zip_with(|x, y| x * y, mu, &w).sum()
+}
+
+/// Minimize `w'Σw - tilt * mu'w` on the bounded simplex (accelerated projected gradient)
+fn mean_variance(mu: &[f64], cov: &Matrix, tilt: f64, bounds: WeightBounds) -> Vec<f64> {
+    let n = mu.len();
+    // Lipschitz constant of the gradient (Gershgorin bound of 2Σ)
+    let lipschitz = (0..n)
+        .map(|i| (0..n).map(|j| cov[(i, j)].abs()).sum::<f64>())
+        .fold(1e-12, f64::max)
+        * 2f64;
+    let step = 1f64 / lipschitz;
+
+    let mut w = project_simplex(&vec![1f64 / n as f64; n], bounds);
+    let mut y = w.clone();
+    let mut t = 1f64;
+    for _ in 0..10000 {
+        let grad = zip_with(|s, m| 2f64 * s - tilt * m, &mat_vec(cov, &y), mu);
+        let w_new = project_simplex(&zip_with(|y, g| y - step * g, &y, &grad), bounds);
+        let t_new = (1f64 + (1f64 + 4f64 * t * t).sqrt()) / 2f64;
+        y = zip_with(|a, b| a + (t - 1f64) / t_new * (a - b), &w_new, &w);
+        let diff = zip_with(|a, b| (a - b).abs(), &w_new, &w).max();
+        w = w_new;
+        t = t_new;
+        if diff < 1e-12 {
+            break;
+        }
+    }
+    w
+}
+
+/// Euclidean projection onto `{w | sum(w) = 1, lower <= w <= upper}`
+fn project_simplex(v: &[f64], bounds: WeightBounds) -> Vec<f64> {
+    let clamp = |tau: f64| {
+        v.to_vec()
+            .fmap(|x| (x - tau).clamp(bounds.lower, bounds.upper))
+    };
+    let mut lo = v.to_vec().min() - bounds.upper;
+    let mut hi = v.to_vec().max() - bounds.lower;
+    for _ in 0..100 {
+        let mid = 0.5 * (lo + hi);
+        if clamp(mid).sum() > 1f64 {
+            lo = mid;
+        } else {
+            hi = mid;
+        }
+    }
+    clamp(0.5 * (lo + hi))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_close(actual: &[f64], expected: &[f64], tol: f64) {
+        assert_eq!(actual.len(), expected.len());
+        for (a, e) in actual.iter().zip(expected.iter()) {
+            assert!((a - e).abs() <= tol, "{:?} != {:?}", actual, expected);
+        }
+    }
+
+    /// Uncorrelated assets with volatilities 10%, 30% and 40%
+    fn diag_cov() -> Matrix {
+        matrix(
+            vec![0.01, 0.0, 0.0, 0.0, 0.09, 0.0, 0.0, 0.0, 0.16],
+            3,
+            3,
+            Row,
+        )
+    }
+
+    #[test]
+    fn inverse_volatility_of_uncorrelated_assets() {
+        // 1/σ = 10, 10/3, 2.5 (12 : 4 : 3)
+        let w = inverse_volatility(&diag_cov());
+        assert_close(&w, &[12f64 / 19f64, 4f64 / 19f64, 3f64 / 19f64], 1e-12);
+    }
+
+    #[test]
+    fn risk_parity_equalizes_risk_contributions() {
+        let cov = matrix(
+            vec![0.04, 0.018, 0.004, 0.018, 0.09, -0.012, 0.004, -0.012, 0.01],
+            3,
+            3,
+            Row,
+        );
+        let w = risk_parity(&cov).unwrap();
+        assert!((w.iter().sum::<f64>() - 1f64).abs() < 1e-12);
+        assert_close(&risk_contribution(&cov, &w), &[1f64 / 3f64; 3], 1e-9);
+        let w = risk_budget(&cov, &[2.0, 1.0, 1.0]).unwrap();
+        assert_close(&risk_contribution(&cov, &w), &[0.5, 0.25, 0.25], 1e-9);
+    }
+
+    #[test]
+    fn hrp_of_block_diagonal_cov() {
+        // Blocks {0, 1} (ρ = 0.5) and {2, 3} (ρ = 0.3) are split first, each asset of a block
+        // has the same variance so the block weight is shared equally
+        let cov = matrix(
+            vec![
+                0.04, 0.02, 0.0, 0.0, //
+                0.02, 0.04, 0.0, 0.0, //
+                0.0, 0.0, 0.09, 0.027, //
+                0.0, 0.0, 0.027, 0.09,
+            ],
+            4,
+            4,
+            Row,
+        );
+        let w = hrp(&cov).unwrap();
+        let (var_a, var_b) = (0.03, 0.0585);
+        let alpha = 1f64 - var_a / (var_a + var_b);
+        let expected = [
+            alpha / 2f64,
+            alpha / 2f64,
+            (1f64 - alpha) / 2f64,
+            (1f64 - alpha) / 2f64,
+        ];
+        assert_close(&w, &expected, 1e-12);
+    }
+
+    #[test]
+    fn hrp_rejects_zero_variance() {
+        let cov = matrix(vec![0.04, 0.0, 0.0, 0.0], 2, 2, Row);
+        assert!(hrp(&cov).is_err());
+        assert!(risk_parity(&cov).is_err());
+    }
+
+    #[test]
+    fn min_variance_of_two_assets() {
+        // w1 = (σ2² - σ12) / (σ1² + σ2² - 2σ12)
+        let cov = matrix(vec![0.04, 0.006, 0.006, 0.09], 2, 2, Row);
+        let w = optimize(
+            &[0.05, 0.08],
+            &cov,
+            Objective::MinVariance,
+            WeightBounds::long_only(),
+        )
+        .unwrap();
+        let w1 = (0.09 - 0.006) / (0.04 + 0.09 - 2f64 * 0.006);
+        assert_close(&w, &[w1, 1f64 - w1], 1e-8);
+    }
+
+    #[test]
+    fn min_variance_respects_bounds() {
+        // Unbounded, the first asset would take 85%; the rest splits by inverse variance
+        let bounds = WeightBounds::new(0.1, 0.4);
+        let w = optimize(&[0.05; 3], &diag_cov(), Objective::MinVariance, bounds).unwrap();
+        assert_close(&w, &[0.4, 0.384, 0.216], 1e-8);
+    }
+
+    #[test]
+    fn weights_sum_to_one_within_bounds() {
+        let mu = [0.04, 0.10, 0.15];
+        let bounds = WeightBounds::new(0.1, 0.5);
+        for objective in [
+            Objective::MinVariance,
+            Objective::MaxSharpe(0.02),
+            Objective::TargetReturn(0.11),
+            Objective::MeanVariance(3.0),
+        ] {
+            let w = optimize(&mu, &diag_cov(), objective, bounds).unwrap();
+            assert!(
+                (w.iter().sum::<f64>() - 1f64).abs() < 1e-9,
+                "{:?}",
+                objective
+            );
+            assert!(w.iter().all(|&x| (0.1 - 1e-9..=0.5 + 1e-9).contains(&x)));
+        }
+        let w = optimize(&mu, &diag_cov(), Objective::TargetReturn(0.11), bounds).unwrap();
+        assert!(portfolio_performance(&mu, &diag_cov(), &w).0 >= 0.11 - 1e-9);
+    }
+
+    #[test]
+    fn unattainable_target_return_is_rejected() {
+        // At most 0.4 * 15% + 0.4 * 10% + 0.2 * 5% = 11%
+        let bounds = WeightBounds::new(0.2, 0.4);
+        let mu = [0.05, 0.10, 0.15];
+        assert!(optimize(&mu, &diag_cov(), Objective::TargetReturn(0.12), bounds).is_err());
+        assert!(optimize(&mu, &diag_cov(), Objective::TargetReturn(0.11), bounds).is_ok());
+        let mu = [0.05, f64::NAN, 0.15];
+        assert!(optimize(&mu, &diag_cov(), Objective::TargetReturn(0.1), bounds).is_err());
+    }
+}
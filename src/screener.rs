@@ -0,0 +1,130 @@
+use crate::api::{download_stocks, HistoricalChart};
+use crate::batch::{compute_batch, Indicator, IndicatorTable};
+use crate::condition::{indicators_of, Condition, Operand};
+use peroxide::fuga::*;
+use std::cmp::Ordering;
+
+/// Stock Screener
+///
+/// - condition : condition which a bar should satisfy
+/// - rank_by : score to rank the matches
+/// - descending : rank higher scores first
+pub struct Screener {
+    pub condition: Condition,
+    pub rank_by: Operand,
+    pub descending: bool,
+}
+
+impl Screener {
+    pub fn new(condition: Condition, rank_by: Operand, descending: bool) -> Self {
+        Self {
+            condition,
+            rank_by,
+            descending,
+        }
+    }
+
+    /// Indicators to compute for the condition & ranking
+    pub fn get_indicators(&self) -> Vec<Indicator> {
+        let mut operands = vec![Operand::close()];
+        operands.extend(self.condition.operands());
+        operands.push(self.rank_by);
+        indicators_of(&operands)
+    }
+
+    /// Matches on the latest date of the universe, ranked across symbols
+    ///
+    /// - Symbols without a bar on that date (stale data) are skipped
+    pub fn screen_latest(&self, hists: &[HistoricalChart]) -> DataFrame {
+        let tables = compute_batch(hists, &self.get_indicators());
+        let latest = tables.iter().filter_map(|t| t.date.last()).max().cloned();
+        let matches = tables
+            .iter()
+            .filter_map(|t| {
+                let i = t.date.len().checked_sub(1)?;
+                let is_latest = Some(&t.date[i]) == latest.as_ref();
+                (is_latest && self.condition.evaluate(t, i)).then_some((t, i))
+            })
+            .collect::<Vec<(&IndicatorTable, usize)>>();
+        self.to_dataframe(matches)
+    }
+
+    /// Matches on every bar of each symbol (for event studies), ranked within each date
+    pub fn screen_history(&self, hists: &[HistoricalChart]) -> DataFrame {
+        let tables = compute_batch(hists, &self.get_indicators());
+        let matches = tables
+            .iter()
+            .flat_map(|t| {
+                (0..t.date.len())
+                    .filter(|&i| self.condition.evaluate(t, i))
+                    .map(move |i| (t, i))
+            })
+            .collect::<Vec<(&IndicatorTable, usize)>>();
+        self.to_dataframe(matches)
+    }
+
+    /// Download the universe and screen the latest bar
+    pub async fn screen_universe(
+        &self,
+        symbols: &[String],
+        from: &str,
+        to: &str,
+    ) -> Result<DataFrame, Box<dyn std::error::Error>> {
+        let hists = download_stocks(symbols, from, to).await?;
+        Ok(self.screen_latest(&hists))
+    }
+
+    /// Sort matches by (date, rank) and collect them with their indicator values
+    fn to_dataframe(&self, matches: Vec<(&IndicatorTable, usize)>) -> DataFrame {
+        let mut scored = matches
+            .into_iter()
+            .map(|(t, i)| (t, i, self.rank_by.value(t, i)))
+            .collect::<Vec<(&IndicatorTable, usize, f64)>>();
+        let descending = self.descending;
+        scored.sort_by(|a, b| {
+            a.0.date[a.1].cmp(&b.0.date[b.1]).then_with(|| {
+                // NaN scores always go last
+                match (a.2.is_nan(), b.2.is_nan()) {
+                    (true, true) => Ordering::Equal,
+                    (true, false) => Ordering::Greater,
+                    (false, true) => Ordering::Less,
+                    _ if descending => b.2.partial_cmp(&a.2).unwrap(),
+                    _ => a.2.partial_cmp(&b.2).unwrap(),
+                }
+            })
+        });
+
+        let header = scored
+            .first()
+            .map(|(t, _, _)| t.header.clone())
+            .unwrap_or_default();
+        let mut symbol = vec![];
+        let mut date: Vec<String> = vec![];
+        let mut rank = vec![];
+        let mut score = vec![];
+        let mut data = vec![vec![]; header.len()];
+        for (t, i, s) in scored {
+            let r = match date.last() {
+                Some(d) if d == &t.date[i] => rank.last().unwrap() + 1,
+                _ => 1usize,
+            };
+            symbol.push(t.symbol.clone());
+            date.push(t.date[i].clone());
+            rank.push(r);
+            score.push(s);
+            for (col, v) in data.iter_mut().zip(t.data.iter()) {
+                col.push(v[i]);
+            }
+        }
+
+        let mut df = DataFrame::new(vec![]);
+        df.push("symbol", Series::new(symbol));
+        df.push("date", Series::new(date));
+        df.push("rank", Series::new(rank));
+        df.push("score", Series::new(score));
+        for (name, col) in header.iter().zip(data) {
+            df.push(name, Series::new(col));
+        }
+        df
+    }
+}
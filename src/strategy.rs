@@ -1,25 +1,54 @@
 use crate::{
-    api::Chart,
-    trade::{Order, Portfolio},
+    api::{next_business_day, parse_date, Chart, HistoricalChart, Timeframe},
+    batch::{Indicator, IndicatorTable},
+    condition::Condition,
+    pairs::{engle_granger, hedge_ratio, zscore},
+    portfolio::{price_returns, Allocation},
+    ta::{adx_dmi, ma, macd, MAType},
+    trade::{annualized_volatility, MarketData, Order, Portfolio},
 };
 #[allow(unused_imports)]
 use peroxide::fuga::*;
+use serde::Deserialize;
 use std::collections::HashMap;
+use std::sync::Arc;
+use time::Weekday;
 
 // ┌──────────────────────────────────────────────────────────┐
 //  Rebalance Strategy
 // └──────────────────────────────────────────────────────────┘
+/// - dates : trading dates up to the current bar (`dates[timestamp - 1]` is the current date)
+/// - Threshold & band rebalances measure drift from fixed targets, so strategies with moving targets
+///   (scheduled, allocation, momentum) should use rebalances without targets (e.g. periodic, calendar)
 pub trait RebalanceStrategy {
     fn should_rebalance(
         &self,
         timestamp: usize,
+        dates: &[String],
         chart_map: &HashMap<String, Chart>,
         portfolio: &Portfolio,
     ) -> bool;
     fn reset(&mut self);
+    fn box_clone(&self) -> Box<dyn RebalanceStrategy>;
+
+    /// Weights to trade to instead of a full rebalance (`None` for a full rebalance)
+    fn partial_weight(
+        &self,
+        _chart_map: &HashMap<String, Chart>,
+        _portfolio: &Portfolio,
+    ) -> Option<HashMap<String, f64>> {
+        None
+    }
+}
+
+impl Clone for Box<dyn RebalanceStrategy> {
+    fn clone(&self) -> Self {
+        self.box_clone()
+    }
 }
 
 /// Periodic Rebalance
+#[derive(Debug, Clone)]
 pub struct PeriodicRebalance {
     pub period: usize,
 }
@@ -34,19 +63,23 @@ impl RebalanceStrategy for PeriodicRebalance {
     fn should_rebalance(
         &self,
         timestamp: usize,
+        _dates: &[String],
         _chart_map: &HashMap<String, Chart>,
         _portfolio: &Portfolio,
     ) -> bool {
         timestamp % self.period == 0
     }
-    fn reset(&mut self) {
-        unimplemented!()
+    fn reset(&mut self) {}
+
+    fn box_clone(&self) -> Box<dyn RebalanceStrategy> {
+        Box::new(self.clone())
     }
 }
 
 /// Threshold Rebalance
 ///
 /// - threshold : percentage of allocation deviation
+#[derive(Debug, Clone)]
 pub struct ThresholdRebalance {
     pub threshold: f64,
     initial_weight: HashMap<String, f64>,
@@ -69,6 +102,7 @@ impl RebalanceStrategy for ThresholdRebalance {
     fn should_rebalance(
         &self,
         _timestamp: usize,
+        _dates: &[String],
         chart_map: &HashMap<String, Chart>,
         portfolio: &Portfolio,
     ) -> bool {
@@ -102,27 +136,216 @@ impl RebalanceStrategy for ThresholdRebalance {
         alloc_dev > self.threshold
     }
 
-    fn reset(&mut self) {
-        unimplemented!()
+    fn reset(&mut self) {}
+
+    fn box_clone(&self) -> Box<dyn RebalanceStrategy> {
+        Box::new(self.clone())
+    }
+}
+
+/// Tolerance band around a target weight
+///
+/// - absolute : absolute deviation (e.g. 0.05 for 5%p)
+/// - relative : deviation relative to the target (e.g. 0.25 for 25%)
+/// - The narrower of the two applies (e.g. 5/25 rule)
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize)]
+pub struct Band {
+    pub absolute: f64,
+    pub relative: f64,
+}
+
+impl Band {
+    pub fn new(absolute: f64, relative: f64) -> Self {
+        Self { absolute, relative }
+    }
+
+    pub fn width(&self, target: f64) -> f64 {
+        self.absolute.min(self.relative * target)
+    }
+}
+
+/// Band Rebalance
+///
+/// - target_weight : target weight of each symbol (the rest is cash)
+/// - default_band : band of symbols without their own band
+/// - partial : trade only the breaching symbols back to the band edge
+#[derive(Debug, Clone)]
+pub struct BandRebalance {
+    pub target_weight: HashMap<String, f64>,
+    pub default_band: Band,
+    pub partial: bool,
+    band: HashMap<String, Band>,
+}
+
+impl BandRebalance {
+    pub fn new(target_weight: &HashMap<String, f64>, default_band: Band, partial: bool) -> Self {
+        Self {
+            target_weight: target_weight.clone(),
+            default_band,
+            partial,
+            band: HashMap::new(),
+        }
+    }
+
+    pub fn set_band(&mut self, symbol: &str, band: Band) {
+        self.band.insert(symbol.to_string(), band);
+    }
+
+    pub fn get_band(&self, symbol: &str) -> Band {
+        self.band.get(symbol).cloned().unwrap_or(self.default_band)
+    }
+
+    /// (lower, upper) edges of the band of a symbol
+    pub fn get_range(&self, symbol: &str) -> (f64, f64) {
+        let target = self.target_weight.get(symbol).cloned().unwrap_or(0f64);
+        let width = self.get_band(symbol).width(target);
+        (target - width, target + width)
+    }
+}
+
+impl RebalanceStrategy for BandRebalance {
+    fn should_rebalance(
+        &self,
+        _timestamp: usize,
+        _dates: &[String],
+        chart_map: &HashMap<String, Chart>,
+        portfolio: &Portfolio,
+    ) -> bool {
+        portfolio_weight(chart_map, portfolio)
+            .iter()
+            .any(|(symbol, &w)| {
+                let (lower, upper) = self.get_range(symbol);
+                w < lower || w > upper
+            })
+    }
+
+    fn reset(&mut self) {}
+
+    fn box_clone(&self) -> Box<dyn RebalanceStrategy> {
+        Box::new(self.clone())
+    }
+
+    fn partial_weight(
+        &self,
+        chart_map: &HashMap<String, Chart>,
+        portfolio: &Portfolio,
+    ) -> Option<HashMap<String, f64>> {
+        if !self.partial {
+            return None;
+        }
+        let weight = portfolio_weight(chart_map, portfolio)
+            .into_iter()
+            .filter_map(|(symbol, w)| {
+                let (lower, upper) = self.get_range(&symbol);
+                (w < lower || w > upper).then(|| (symbol, w.clamp(lower, upper)))
+            })
+            .collect();
+        Some(weight)
+    }
+}
+
+/// Calendar event which triggers a rebalance
+///
+/// - First : first trading day of each period
+/// - Last : last trading day of each period
+/// - Weekday : every given weekday
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CalendarTrigger {
+    First(Timeframe),
+    Last(Timeframe),
+    Weekday(Weekday),
+}
+
+/// Calendar Rebalance
+///
+/// - First : the period differs from the previous trading date (the first date of the backtest is not a boundary)
+/// - Last : the next business day is in another period, decided without future rows
+/// - Last on a period ending with a holiday falls back to the first trading day of the next period
+#[derive(Debug, Clone)]
+pub struct CalendarRebalance {
+    pub trigger: CalendarTrigger,
+}
+
+impl CalendarRebalance {
+    pub fn new(trigger: CalendarTrigger) -> Self {
+        Self { trigger }
+    }
+}
+
+impl RebalanceStrategy for CalendarRebalance {
+    fn should_rebalance(
+        &self,
+        timestamp: usize,
+        dates: &[String],
+        _chart_map: &HashMap<String, Chart>,
+        _portfolio: &Portfolio,
+    ) -> bool {
+        let i = timestamp - 1;
+        match self.trigger {
+            CalendarTrigger::First(timeframe) => {
+                i > 0 && timeframe.key(&dates[i]).ok() != timeframe.key(&dates[i - 1]).ok()
+            }
+            CalendarTrigger::Last(timeframe) => {
+                let is_last = |date: &str| {
+                    parse_date(date)
+                        .map(|d| timeframe.key_of(d) != timeframe.key_of(next_business_day(d)))
+                        .unwrap_or(false)
+                };
+                let missed = i > 0
+                    && timeframe.key(&dates[i]).ok() != timeframe.key(&dates[i - 1]).ok()
+                    && !is_last(&dates[i - 1]);
+                is_last(&dates[i]) || missed
+            }
+            CalendarTrigger::Weekday(weekday) => parse_date(&dates[i])
+                .map(|d| d.weekday() == weekday)
+                .unwrap_or(false),
+        }
+    }
+
+    fn reset(&mut self) {}
+
+    fn box_clone(&self) -> Box<dyn RebalanceStrategy> {
+        Box::new(self.clone())
     }
 }
 
 // ┌──────────────────────────────────────────────────────────┐
 //  Strategy
 // └──────────────────────────────────────────────────────────┘
+/// - dates : trading dates up to the current bar (`dates[timestamp - 1]` is the current date)
+/// - min_trade (weight-based strategies) : minimum value of an order, smaller orders are skipped
+///   unless the portfolio holds no shares (opening)
 pub trait Strategy {
     fn gen_order_map(
         &mut self,
         timestamp: usize,
+        dates: &[String],
         chart_map: &HashMap<String, Chart>,
         portfolio: &Portfolio,
     ) -> HashMap<String, Order>;
     fn to_string(&self) -> String;
+    /// Clear internal state to run again from the start
+    fn reset(&mut self);
+    /// Precompute indicators over the market data (called by `Backtester::run` after `reset`)
+    ///
+    /// - Values used at `timestamp` should only depend on rows `..timestamp` (up to the current bar)
+    fn prepare(&mut self, _market_data: &MarketData) {}
+    fn box_clone(&self) -> Box<dyn Strategy>;
+}
+
+impl Clone for Box<dyn Strategy> {
+    fn clone(&self) -> Self {
+        self.box_clone()
+    }
 }
 
 /// Buy and Hold
+///
+/// - Rebalances by trading the difference between current holdings and target weights
+#[derive(Clone)]
 pub struct BuyAndHold {
     pub weight: HashMap<String, f64>,
+    pub min_trade: f64,
     rebalance_strategy: Box<dyn RebalanceStrategy>,
     bought: bool,
 }
@@ -134,6 +357,7 @@ impl BuyAndHold {
     ) -> Self {
         Self {
             weight,
+            min_trade: 0f64,
             rebalance_strategy,
             bought: false,
         }
@@ -148,6 +372,7 @@ impl Strategy for BuyAndHold {
     fn gen_order_map(
         &mut self,
         timestamp: usize,
+        dates: &[String],
         chart_map: &HashMap<String, Chart>,
         portfolio: &Portfolio,
     ) -> HashMap<String, Order> {
@@ -169,17 +394,14 @@ impl Strategy for BuyAndHold {
             self.bought = true;
         } else if self
             .rebalance_strategy
-            .should_rebalance(timestamp, chart_map, portfolio)
+            .should_rebalance(timestamp, dates, chart_map, portfolio)
         {
-            // Closing all positions
-            for symbol in symbols.iter() {
-                let current_share = portfolio.get_share(symbol).unwrap();
-                order_map.insert(
-                    symbol.to_string(),
-                    Order::new(symbol, -(current_share as isize)),
-                );
-            }
-            self.bought = false;
+            // Trading from the current holdings to the target (or partial) weights
+            let weight = self
+                .rebalance_strategy
+                .partial_weight(chart_map, portfolio)
+                .unwrap_or_else(|| self.weight.clone());
+            order_map = delta_orders(&weight, self.min_trade, chart_map, portfolio);
         }
         order_map
     }
@@ -187,4 +409,1197 @@ impl Strategy for BuyAndHold {
     fn to_string(&self) -> String {
         "BnH".to_string()
     }
+
+    fn reset(&mut self) {
+        self.bought = false;
+        self.rebalance_strategy.reset();
+    }
+
+    fn box_clone(&self) -> Box<dyn Strategy> {
+        Box::new(self.clone())
+    }
+}
+
+/// Target weight as a function of the date
+pub type WeightFn = Arc<dyn Fn(&str) -> HashMap<String, f64> + Send + Sync>;
+
+/// Time-varying target weights
+///
+/// - Piecewise : (start date, weight) steps, each applies until the next one starts
+/// - Glide : linear path from `start` at `from` to `end` at `to` (constant outside)
+/// - Custom : any function of the date
+#[derive(Clone)]
+pub enum WeightSchedule {
+    Piecewise(Vec<(String, HashMap<String, f64>)>),
+    Glide {
+        from: String,
+        to: String,
+        start: HashMap<String, f64>,
+        end: HashMap<String, f64>,
+    },
+    Custom(WeightFn),
+}
+
+impl WeightSchedule {
+    pub fn piecewise(
+        mut steps: Vec<(String, HashMap<String, f64>)>,
+    ) -> Result<Self, Box<dyn std::error::Error>> {
+        if steps.is_empty() {
+            return Err("piecewise schedule: at least one step is required".into());
+        }
+        for (date, _) in steps.iter() {
+            parse_date(date)?;
+        }
+        steps.sort_by(|a, b| a.0.cmp(&b.0));
+        Ok(WeightSchedule::Piecewise(steps))
+    }
+
+    pub fn glide(
+        from: &str,
+        to: &str,
+        start: HashMap<String, f64>,
+        end: HashMap<String, f64>,
+    ) -> Result<Self, Box<dyn std::error::Error>> {
+        if parse_date(from)? >= parse_date(to)? {
+            return Err("glide schedule: from should be earlier than to".into());
+        }
+        Ok(WeightSchedule::Glide {
+            from: from.to_string(),
+            to: to.to_string(),
+            start,
+            end,
+        })
+    }
+
+    pub fn custom<F: Fn(&str) -> HashMap<String, f64> + Send + Sync + 'static>(f: F) -> Self {
+        WeightSchedule::Custom(Arc::new(f))
+    }
+
+    /// Target weight at `date` (before the first step, the first step applies)
+    pub fn weight(&self, date: &str) -> HashMap<String, f64> {
+        match self {
+            WeightSchedule::Piecewise(steps) => {
+                let k = steps.partition_point(|(d, _)| d.as_str() <= date);
+                steps[k.saturating_sub(1)].1.clone()
+            }
+            WeightSchedule::Glide {
+                from,
+                to,
+                start,
+                end,
+            } => {
+                let day = |d: &str| parse_date(d).unwrap().to_julian_day() as f64;
+                let t = ((day(date) - day(from)) / (day(to) - day(from))).clamp(0f64, 1f64);
+                start
+                    .iter()
+                    .map(|(symbol, &w0)| {
+                        let w1 = end.get(symbol).cloned().unwrap_or(0f64);
+                        (symbol.to_string(), w0 + (w1 - w0) * t)
+                    })
+                    .collect()
+            }
+            WeightSchedule::Custom(f) => f(date),
+        }
+    }
+
+    /// Index of the piecewise step at `date` (`None` for continuous schedules)
+    fn step(&self, date: &str) -> Option<usize> {
+        match self {
+            WeightSchedule::Piecewise(steps) => {
+                Some(steps.partition_point(|(d, _)| d.as_str() <= date))
+            }
+            _ => None,
+        }
+    }
+}
+
+/// Scheduled Weight
+///
+/// - Trades to the scheduled weights whenever `rebalance_strategy` triggers
+/// - Also trades when a piecewise schedule enters a new step
+#[derive(Clone)]
+pub struct ScheduledWeight {
+    pub schedule: WeightSchedule,
+    pub min_trade: f64,
+    rebalance_strategy: Box<dyn RebalanceStrategy>,
+    bought: bool,
+    step: Option<usize>,
+}
+
+impl ScheduledWeight {
+    pub fn new(schedule: WeightSchedule, rebalance_strategy: Box<dyn RebalanceStrategy>) -> Self {
+        Self {
+            schedule,
+            min_trade: 0f64,
+            rebalance_strategy,
+            bought: false,
+            step: None,
+        }
+    }
+}
+
+impl Strategy for ScheduledWeight {
+    fn gen_order_map(
+        &mut self,
+        timestamp: usize,
+        dates: &[String],
+        chart_map: &HashMap<String, Chart>,
+        portfolio: &Portfolio,
+    ) -> HashMap<String, Order> {
+        let date = &dates[timestamp - 1];
+        let step = self.schedule.step(date);
+        let step_changed = step != self.step;
+        self.step = step;
+
+        if !self.bought
+            || step_changed
+            || self
+                .rebalance_strategy
+                .should_rebalance(timestamp, dates, chart_map, portfolio)
+        {
+            self.bought = true;
+            let weight = self.schedule.weight(date);
+            delta_orders(&weight, self.min_trade, chart_map, portfolio)
+        } else {
+            delta_orders(&HashMap::new(), self.min_trade, chart_map, portfolio)
+        }
+    }
+
+    fn to_string(&self) -> String {
+        "Scheduled".to_string()
+    }
+
+    fn reset(&mut self) {
+        self.bought = false;
+        self.step = None;
+        self.rebalance_strategy.reset();
+    }
+
+    fn box_clone(&self) -> Box<dyn Strategy> {
+        Box::new(self.clone())
+    }
+}
+
+/// Allocation recomputed from a rolling window of returns
+///
+/// - Stays in cash until `lookback` returns are available
+/// - Recomputes the weights & trades whenever `rebalance_strategy` triggers
+#[derive(Clone)]
+pub struct AllocationStrategy {
+    pub symbols: Vec<String>,
+    pub allocation: Allocation,
+    pub lookback: usize,
+    pub min_trade: f64,
+    rebalance_strategy: Box<dyn RebalanceStrategy>,
+    history: HashMap<String, Vec<Chart>>,
+    bought: bool,
+}
+
+impl AllocationStrategy {
+    pub fn new(
+        symbols: &[String],
+        allocation: Allocation,
+        lookback: usize,
+        rebalance_strategy: Box<dyn RebalanceStrategy>,
+    ) -> Self {
+        Self {
+            symbols: symbols.to_vec(),
+            allocation,
+            lookback,
+            min_trade: 0f64,
+            rebalance_strategy,
+            history: HashMap::new(),
+            bought: false,
+        }
+    }
+
+    /// Weights from the last `lookback` returns (None during warm-up or on failure)
+    pub fn get_weight(&self) -> Option<HashMap<String, f64>> {
+        let prices = self
+            .symbols
+            .iter()
+            .map(|symbol| {
+                let history = self.history.get(symbol)?;
+                let start = history.len().checked_sub(self.lookback + 1)?;
+                Some(history[start..].iter().map(|c| c.adj_close).collect())
+            })
+            .collect::<Option<Vec<Vec<f64>>>>()?;
+        let returns = price_returns(&prices).ok()?;
+        let weight = self.allocation.allocate(&returns).ok()?;
+        Some(self.symbols.iter().cloned().zip(weight).collect())
+    }
+}
+
+impl Strategy for AllocationStrategy {
+    fn gen_order_map(
+        &mut self,
+        timestamp: usize,
+        dates: &[String],
+        chart_map: &HashMap<String, Chart>,
+        portfolio: &Portfolio,
+    ) -> HashMap<String, Order> {
+        record_history(&mut self.history, chart_map);
+        let should_rebalance = self
+            .rebalance_strategy
+            .should_rebalance(timestamp, dates, chart_map, portfolio);
+        let weight = if !self.bought || should_rebalance {
+            self.get_weight()
+        } else {
+            None
+        };
+        if weight.is_some() {
+            self.bought = true;
+        }
+        delta_orders(
+            &weight.unwrap_or_default(),
+            self.min_trade,
+            chart_map,
+            portfolio,
+        )
+    }
+
+    fn to_string(&self) -> String {
+        format!("{:?}", self.allocation)
+    }
+
+    fn reset(&mut self) {
+        self.history.clear();
+        self.bought = false;
+        self.rebalance_strategy.reset();
+    }
+
+    fn box_clone(&self) -> Box<dyn Strategy> {
+        Box::new(self.clone())
+    }
+}
+
+/// Volatility targeting overlay
+///
+/// - The inner strategy trades a frictionless shadow portfolio which defines its target exposure
+/// - Exposure is scaled by `target_volatility / realized volatility` of the shadow portfolio (last `window` returns)
+/// - max_leverage : cap of the total exposure (above 1 borrows at the backtester's `borrow_rate`)
+/// - The rest is held in cash which earns `interest_rate`
+/// - Trades when the inner strategy trades or `rebalance_strategy` triggers
+#[derive(Clone)]
+pub struct VolatilityTarget {
+    pub target_volatility: f64,
+    pub window: usize,
+    pub max_leverage: f64,
+    pub min_trade: f64,
+    strategy: Box<dyn Strategy>,
+    rebalance_strategy: Box<dyn RebalanceStrategy>,
+    shadow: Option<Portfolio>,
+    value_history: Vec<f64>,
+}
+
+impl VolatilityTarget {
+    pub fn new(
+        strategy: Box<dyn Strategy>,
+        target_volatility: f64,
+        window: usize,
+        max_leverage: f64,
+        rebalance_strategy: Box<dyn RebalanceStrategy>,
+    ) -> Self {
+        Self {
+            target_volatility,
+            window,
+            max_leverage,
+            min_trade: 0f64,
+            strategy,
+            rebalance_strategy,
+            shadow: None,
+            value_history: vec![],
+        }
+    }
+
+    /// Realized volatility of the inner strategy (None during warm-up)
+    pub fn get_volatility(&self) -> Option<f64> {
+        let len = self.value_history.len();
+        if self.window < 2 || len < self.window + 1 {
+            return None;
+        }
+        let daily_return = self.value_history[len - self.window - 1..]
+            .windows(2)
+            .map(|v| v[1] / v[0] - 1f64)
+            .collect::<Vec<f64>>();
+        Some(annualized_volatility(&daily_return))
+    }
+
+    /// Scale of the inner exposure (1 during warm-up, bounded by `max_leverage`)
+    pub fn get_scale(&self, exposure: f64) -> f64 {
+        let scale = match self.get_volatility() {
+            Some(vol) if vol > 0f64 => self.target_volatility / vol,
+            _ => 1f64,
+        };
+        if exposure > 0f64 {
+            scale.min(self.max_leverage / exposure)
+        } else {
+            scale
+        }
+    }
+}
+
+impl Strategy for VolatilityTarget {
+    fn gen_order_map(
+        &mut self,
+        timestamp: usize,
+        dates: &[String],
+        chart_map: &HashMap<String, Chart>,
+        portfolio: &Portfolio,
+    ) -> HashMap<String, Order> {
+        let shadow = self.shadow.get_or_insert_with(|| portfolio.clone());
+        let inner_order = self
+            .strategy
+            .gen_order_map(timestamp, dates, chart_map, shadow);
+        let mut inner_traded = false;
+        for (symbol, order) in inner_order.iter() {
+            if order.shares == 0 {
+                continue;
+            }
+            let price = chart_map.get(symbol).unwrap().adj_close;
+            let share = shadow.get_share(symbol).unwrap() + order.shares;
+            shadow.update_share(symbol, share);
+            shadow.update_balance(shadow.get_balance() - price * order.shares as f64);
+            inner_traded = true;
+        }
+        let weight = portfolio_weight(chart_map, shadow);
+        self.value_history.push(shadow.value(chart_map));
+
+        let should_rebalance = self
+            .rebalance_strategy
+            .should_rebalance(timestamp, dates, chart_map, portfolio);
+        let weight = if inner_traded || should_rebalance {
+            let scale = self.get_scale(weight.values().sum::<f64>());
+            weight
+                .into_iter()
+                .map(|(s, w)| (s, w * scale))
+                .collect::<HashMap<String, f64>>()
+        } else {
+            HashMap::new()
+        };
+        delta_orders(&weight, self.min_trade, chart_map, portfolio)
+    }
+
+    fn to_string(&self) -> String {
+        format!("VolTarget({})", self.strategy.to_string())
+    }
+
+    fn reset(&mut self) {
+        self.shadow = None;
+        self.value_history.clear();
+        self.strategy.reset();
+        self.rebalance_strategy.reset();
+    }
+
+    fn box_clone(&self) -> Box<dyn Strategy> {
+        Box::new(self.clone())
+    }
+}
+
+/// Momentum selection rule
+///
+/// - CrossSectional : equal weight on the `top` symbols of `universe` by momentum
+/// - Absolute : equal weight on each symbol of `universe`, replaced by `fallback` unless it beats the hurdle
+/// - Dual : cross-sectional `top`, then each pick is replaced by `fallback` unless it beats the hurdle
+/// - GEM : Global Equities Momentum (`us` beats the hurdle ? best of `us` & `intl` : `bond`)
+/// - fallback : `None` for cash
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum MomentumRule {
+    CrossSectional {
+        universe: Vec<String>,
+        top: usize,
+    },
+    Absolute {
+        universe: Vec<String>,
+        fallback: Option<String>,
+    },
+    Dual {
+        universe: Vec<String>,
+        top: usize,
+        fallback: Option<String>,
+    },
+    #[serde(rename = "gem")]
+    GEM {
+        us: String,
+        intl: String,
+        bond: String,
+    },
+}
+
+/// Momentum rotation
+///
+/// - Momentum : trailing return over `lookback` bars, skipping the last `skip` bars (e.g. 252 & 21)
+/// - hurdle : symbol whose momentum is the absolute threshold (e.g. T-bill ETF), `None` for 0
+/// - Stays in cash until enough history is available
+/// - Rotates whenever `rebalance_strategy` triggers (e.g. calendar)
+#[derive(Clone)]
+pub struct Momentum {
+    pub rule: MomentumRule,
+    pub lookback: usize,
+    pub skip: usize,
+    pub hurdle: Option<String>,
+    pub min_trade: f64,
+    rebalance_strategy: Box<dyn RebalanceStrategy>,
+    history: HashMap<String, Vec<Chart>>,
+    bought: bool,
+}
+
+impl Momentum {
+    pub fn new(
+        rule: MomentumRule,
+        lookback: usize,
+        skip: usize,
+        rebalance_strategy: Box<dyn RebalanceStrategy>,
+    ) -> Self {
+        Self {
+            rule,
+            lookback,
+            skip,
+            hurdle: None,
+            min_trade: 0f64,
+            rebalance_strategy,
+            history: HashMap::new(),
+            bought: false,
+        }
+    }
+
+    /// Trailing return of a symbol (None during warm-up)
+    pub fn get_momentum(&self, symbol: &str) -> Option<f64> {
+        let history = self.history.get(symbol)?;
+        let end = history.len().checked_sub(self.skip + 1)?;
+        let start = end.checked_sub(self.lookback)?;
+        Some(history[end].adj_close / history[start].adj_close - 1f64)
+    }
+
+    /// Target weights of the selected symbols (None during warm-up)
+    pub fn get_weight(&self) -> Option<HashMap<String, f64>> {
+        let hurdle = match &self.hurdle {
+            Some(symbol) => self.get_momentum(symbol)?,
+            None => 0f64,
+        };
+        let picks = match &self.rule {
+            MomentumRule::CrossSectional { universe, top } => self
+                .rank(universe, *top)?
+                .into_iter()
+                .map(|(symbol, _)| Some(symbol))
+                .collect::<Vec<Option<String>>>(),
+            MomentumRule::Absolute { universe, fallback } => {
+                absolute_filter(self.rank(universe, universe.len())?, hurdle, fallback)
+            }
+            MomentumRule::Dual {
+                universe,
+                top,
+                fallback,
+            } => absolute_filter(self.rank(universe, *top)?, hurdle, fallback),
+            MomentumRule::GEM { us, intl, bond } => {
+                let us_score = self.get_momentum(us)?;
+                let intl_score = self.get_momentum(intl)?;
+                let pick = if us_score <= hurdle {
+                    bond
+                } else if intl_score > us_score {
+                    intl
+                } else {
+                    us
+                };
+                vec![Some(pick.to_string())]
+            }
+        };
+        let mut weight = HashMap::new();
+        for symbol in picks.iter().flatten() {
+            *weight.entry(symbol.to_string()).or_insert(0f64) += 1f64 / picks.len() as f64;
+        }
+        Some(weight)
+    }
+
+    /// Top `n` symbols by momentum (descending, non-finite scores are dropped)
+    fn rank(&self, universe: &[String], n: usize) -> Option<Vec<(String, f64)>> {
+        let mut scores = universe
+            .iter()
+            .map(|symbol| Some((symbol.to_string(), self.get_momentum(symbol)?)))
+            .collect::<Option<Vec<(String, f64)>>>()?;
+        scores.retain(|(_, score)| score.is_finite());
+        scores.sort_by(|a, b| b.1.total_cmp(&a.1));
+        scores.truncate(n);
+        if scores.is_empty() {
+            None
+        } else {
+            Some(scores)
+        }
+    }
+}
+
+impl Strategy for Momentum {
+    fn gen_order_map(
+        &mut self,
+        timestamp: usize,
+        dates: &[String],
+        chart_map: &HashMap<String, Chart>,
+        portfolio: &Portfolio,
+    ) -> HashMap<String, Order> {
+        record_history(&mut self.history, chart_map);
+        let should_rebalance = self
+            .rebalance_strategy
+            .should_rebalance(timestamp, dates, chart_map, portfolio);
+        let selected = if !self.bought || should_rebalance {
+            self.get_weight()
+        } else {
+            None
+        };
+        // Symbols which are not selected are sold
+        let weight = match selected {
+            Some(selected) => {
+                self.bought = true;
+                portfolio
+                    .get_symbols()
+                    .into_iter()
+                    .map(|symbol| {
+                        let w = selected.get(&symbol).cloned().unwrap_or(0f64);
+                        (symbol, w)
+                    })
+                    .collect::<HashMap<String, f64>>()
+            }
+            None => HashMap::new(),
+        };
+        delta_orders(&weight, self.min_trade, chart_map, portfolio)
+    }
+
+    fn to_string(&self) -> String {
+        match self.rule {
+            MomentumRule::CrossSectional { .. } => "Momentum".to_string(),
+            MomentumRule::Absolute { .. } => "AbsoluteMomentum".to_string(),
+            MomentumRule::Dual { .. } => "DualMomentum".to_string(),
+            MomentumRule::GEM { .. } => "GEM".to_string(),
+        }
+    }
+
+    fn reset(&mut self) {
+        self.history.clear();
+        self.bought = false;
+        self.rebalance_strategy.reset();
+    }
+
+    fn box_clone(&self) -> Box<dyn Strategy> {
+        Box::new(self.clone())
+    }
+}
+
+/// Pairs trading on the spread `y - alpha - beta * x`
+///
+/// - Hedge ratio & z-score are estimated on the last `lookback` bars
+/// - Short the spread (short `y`, long `beta` x) above `entry_z`, long below `-entry_z`
+/// - Exit when the z-score returns within `exit_z`, or beyond `stop_z` (stop loss)
+/// - While in a trade, alpha & beta stay at their entry values and the z-score is that of the
+///   fixed spread against its mean & sd over the last `lookback` bars
+/// - weight : gross exposure of both legs as a fraction of the portfolio value
+/// - significance : enter only if the Engle-Granger test (`lags`) rejects at 0.01, 0.05 or 0.1
+#[derive(Debug, Clone)]
+pub struct PairsTrading {
+    pub y: String,
+    pub x: String,
+    pub lookback: usize,
+    pub entry_z: f64,
+    pub exit_z: f64,
+    pub stop_z: Option<f64>,
+    pub weight: f64,
+    pub significance: Option<f64>,
+    pub lags: usize,
+    history: Vec<(f64, f64)>,
+    position: Option<PairPosition>,
+}
+
+/// Open trade of a pair: target shares of both legs & the spread fixed at entry
+#[derive(Debug, Clone, Copy)]
+struct PairPosition {
+    y: isize,
+    x: isize,
+    alpha: f64,
+    beta: f64,
+}
+
+impl PairsTrading {
+    pub fn new(y: &str, x: &str, lookback: usize, entry_z: f64, exit_z: f64, weight: f64) -> Self {
+        Self {
+            y: y.to_string(),
+            x: x.to_string(),
+            lookback,
+            entry_z,
+            exit_z,
+            stop_z: None,
+            weight,
+            significance: None,
+            lags: 1,
+            history: vec![],
+            position: None,
+        }
+    }
+
+    /// (alpha, hedge ratio, z-score of the spread) fitted over the last `lookback` bars
+    pub fn get_spread(&self) -> Option<(f64, f64, f64)> {
+        let start = self.history.len().checked_sub(self.lookback)?;
+        let (py, px): (Vec<f64>, Vec<f64>) = self.history[start..].iter().cloned().unzip();
+        let (alpha, beta, residuals) = hedge_ratio(&py, &px).ok()?;
+        Some((alpha, beta, zscore(&residuals)))
+    }
+
+    /// z-score of the spread `y - alpha - beta * x` over the last `lookback` bars
+    pub fn get_fixed_zscore(&self, alpha: f64, beta: f64) -> Option<f64> {
+        let start = self.history.len().checked_sub(self.lookback)?;
+        let spread = self.history[start..]
+            .iter()
+            .map(|(py, px)| py - alpha - beta * px)
+            .collect::<Vec<f64>>();
+        Some(zscore(&spread))
+    }
+
+    fn is_cointegrated(&self) -> bool {
+        let significance = match self.significance {
+            Some(significance) => significance,
+            None => return true,
+        };
+        let start = self.history.len().saturating_sub(self.lookback);
+        let (py, px): (Vec<f64>, Vec<f64>) = self.history[start..].iter().cloned().unzip();
+        engle_granger(&py, &px, self.lags)
+            .and_then(|eg| eg.test.reject(significance))
+            .unwrap_or(false)
+    }
+}
+
+impl Strategy for PairsTrading {
+    fn gen_order_map(
+        &mut self,
+        _timestamp: usize,
+        _dates: &[String],
+        chart_map: &HashMap<String, Chart>,
+        portfolio: &Portfolio,
+    ) -> HashMap<String, Order> {
+        let mut order_map = portfolio
+            .get_symbols()
+            .iter()
+            .map(|symbol| (symbol.to_string(), Order::new(symbol, 0)))
+            .collect::<HashMap<String, Order>>();
+        let py = chart_map.get(&self.y).unwrap().adj_close;
+        let px = chart_map.get(&self.x).unwrap().adj_close;
+        self.history.push((py, px));
+
+        match self.position {
+            Some(position) => {
+                let z = match self.get_fixed_zscore(position.alpha, position.beta) {
+                    Some(z) if z.is_finite() => z,
+                    _ => return order_map,
+                };
+                let stopped = self.stop_z.map(|stop| z.abs() > stop).unwrap_or(false);
+                let converged = if position.y > 0 {
+                    z >= -self.exit_z
+                } else {
+                    z <= self.exit_z
+                };
+                if converged || stopped {
+                    self.position = None;
+                }
+            }
+            None => {
+                let (alpha, beta, z) = match self.get_spread() {
+                    Some(spread) if spread.2.is_finite() => spread,
+                    _ => return order_map,
+                };
+                let stopped = self.stop_z.map(|stop| z.abs() > stop).unwrap_or(false);
+                if z.abs() > self.entry_z && !stopped && self.is_cointegrated() {
+                    let value = portfolio.value(chart_map);
+                    let side = if z > 0f64 { -1f64 } else { 1f64 };
+                    let n = self.weight * value / (py + beta.abs() * px);
+                    let y = (side * n) as isize;
+                    if y != 0 {
+                        self.position = Some(PairPosition {
+                            y,
+                            x: (-side * beta * n) as isize,
+                            alpha,
+                            beta,
+                        });
+                    }
+                }
+            }
+        }
+
+        let (target_y, target_x) = self.position.map(|p| (p.y, p.x)).unwrap_or((0, 0));
+        for (symbol, target) in [(&self.y, target_y), (&self.x, target_x)] {
+            let shares = target - portfolio.get_share(symbol).unwrap();
+            order_map.insert(symbol.to_string(), Order::new(symbol, shares));
+        }
+        order_map
+    }
+
+    fn to_string(&self) -> String {
+        format!("Pairs({}/{})", self.y, self.x)
+    }
+
+    fn reset(&mut self) {
+        self.history.clear();
+        self.position = None;
+    }
+
+    fn box_clone(&self) -> Box<dyn Strategy> {
+        Box::new(self.clone())
+    }
+}
+
+/// Moving Average Crossover
+///
+/// - Long while the short MA is above the long MA of adj_close, flat otherwise
+/// - weight : capital allocation of each symbol when entering
+#[derive(Clone)]
+pub struct MACrossover {
+    pub short_period: usize,
+    pub long_period: usize,
+    pub ma_type: MAType,
+    pub weight: HashMap<String, f64>,
+    long: HashMap<String, Vec<bool>>,
+}
+
+impl MACrossover {
+    pub fn new(
+        short_period: usize,
+        long_period: usize,
+        ma_type: MAType,
+        weight: HashMap<String, f64>,
+    ) -> Self {
+        Self {
+            short_period,
+            long_period,
+            ma_type,
+            weight,
+            long: HashMap::new(),
+        }
+    }
+}
+
+impl Strategy for MACrossover {
+    fn gen_order_map(
+        &mut self,
+        timestamp: usize,
+        _dates: &[String],
+        chart_map: &HashMap<String, Chart>,
+        portfolio: &Portfolio,
+    ) -> HashMap<String, Order> {
+        let signal = chart_map
+            .keys()
+            .map(|symbol| {
+                let long = self
+                    .long
+                    .get(symbol)
+                    .and_then(|v| v.get(timestamp - 1))
+                    .cloned()
+                    .unwrap_or(false);
+                (symbol.to_string(), long)
+            })
+            .collect::<HashMap<String, bool>>();
+        signal_orders(&signal, chart_map, portfolio, |symbol, value| {
+            value * self.weight.get(symbol).cloned().unwrap_or(0f64)
+        })
+    }
+
+    fn to_string(&self) -> String {
+        format!("MA_CO({},{})", self.short_period, self.long_period)
+    }
+
+    fn reset(&mut self) {
+        self.long.clear();
+    }
+
+    fn prepare(&mut self, market_data: &MarketData) {
+        for (symbol, charts) in adjusted_history(market_data) {
+            let close = charts.iter().map(|c| c.close).collect::<Vec<f64>>();
+            let short_ma = ma(&close, self.short_period, self.ma_type);
+            let long_ma = ma(&close, self.long_period, self.ma_type);
+            let long = (0..close.len())
+                .map(|i| i + 1 >= self.long_period && short_ma[i] > long_ma[i])
+                .collect();
+            self.long.insert(symbol, long);
+        }
+    }
+
+    fn box_clone(&self) -> Box<dyn Strategy> {
+        Box::new(self.clone())
+    }
+}
+
+/// MACD + ADX
+///
+/// - Enter when MACD is above its signal line and ADX confirms the trend
+/// - Exit when MACD falls below its signal line
+/// - Indicators are computed on adjusted prices
+/// - adx_threshold : minimum ADX to enter (e.g. 25)
+/// - weight : capital allocation of each symbol when entering
+#[allow(non_camel_case_types)]
+#[derive(Clone)]
+pub struct MACD_ADX {
+    pub fast_period: usize,
+    pub slow_period: usize,
+    pub signal_period: usize,
+    pub adx_period: usize,
+    pub adx_threshold: f64,
+    pub weight: HashMap<String, f64>,
+    /// (MACD above its signal line, ADX above the threshold) of each bar
+    state: HashMap<String, Vec<(bool, bool)>>,
+}
+
+impl MACD_ADX {
+    pub fn new(
+        fast_period: usize,
+        slow_period: usize,
+        signal_period: usize,
+        adx_period: usize,
+        adx_threshold: f64,
+        weight: HashMap<String, f64>,
+    ) -> Self {
+        Self {
+            fast_period,
+            slow_period,
+            signal_period,
+            adx_period,
+            adx_threshold,
+            weight,
+            state: HashMap::new(),
+        }
+    }
+}
+
+impl Strategy for MACD_ADX {
+    fn gen_order_map(
+        &mut self,
+        timestamp: usize,
+        _dates: &[String],
+        chart_map: &HashMap<String, Chart>,
+        portfolio: &Portfolio,
+    ) -> HashMap<String, Order> {
+        let mut signal = HashMap::new();
+        for symbol in chart_map.keys() {
+            let (bullish, trending) = self
+                .state
+                .get(symbol)
+                .and_then(|v| v.get(timestamp - 1))
+                .cloned()
+                .unwrap_or((false, false));
+            let holding = portfolio.get_share(symbol).unwrap_or(0) > 0;
+            let long = if holding {
+                bullish
+            } else {
+                bullish && trending
+            };
+            signal.insert(symbol.to_string(), long);
+        }
+        signal_orders(&signal, chart_map, portfolio, |symbol, value| {
+            value * self.weight.get(symbol).cloned().unwrap_or(0f64)
+        })
+    }
+
+    fn to_string(&self) -> String {
+        format!(
+            "MACD_ADX({},{},{},{})",
+            self.fast_period, self.slow_period, self.signal_period, self.adx_period
+        )
+    }
+
+    fn reset(&mut self) {
+        self.state.clear();
+    }
+
+    fn prepare(&mut self, market_data: &MarketData) {
+        let warm_up = (self.slow_period + self.signal_period).max(2 * self.adx_period);
+        for (symbol, charts) in adjusted_history(market_data) {
+            let high = charts.iter().map(|c| c.high).collect::<Vec<f64>>();
+            let low = charts.iter().map(|c| c.low).collect::<Vec<f64>>();
+            let close = charts.iter().map(|c| c.close).collect::<Vec<f64>>();
+            let (m, s, _) = macd(
+                &close,
+                self.fast_period,
+                self.slow_period,
+                self.signal_period,
+                MAType::EMA,
+            );
+            let (adx, _, _) = adx_dmi(&high, &low, &close, self.adx_period);
+            let state = (0..close.len())
+                .map(|i| {
+                    if i + 1 < warm_up {
+                        (false, false)
+                    } else {
+                        (m[i] > s[i], adx[i] > self.adx_threshold)
+                    }
+                })
+                .collect();
+            self.state.insert(symbol, state);
+        }
+    }
+
+    fn box_clone(&self) -> Box<dyn Strategy> {
+        Box::new(self.clone())
+    }
+}
+
+/// Position sizing of rule-based entries
+///
+/// - Shares : fixed number of shares
+/// - Amount : fixed amount of capital
+/// - Equity : fraction of the portfolio value
+/// - ATRRisk(risk, period, multiplier) : risk a fraction of the portfolio value with a stop at `multiplier` x ATR
+#[derive(Debug, Clone, Copy)]
+pub enum PositionSizing {
+    Shares(usize),
+    Amount(f64),
+    Equity(f64),
+    ATRRisk(f64, usize, f64),
+}
+
+/// Rules-based Strategy
+///
+/// - entry : condition to open a long position when flat
+/// - exit : condition to close the position when holding
+/// - sizing : position sizing of each entry
+/// - Conditions are evaluated on adjusted prices, computed once per run (custom indicators should be causal)
+#[derive(Clone)]
+pub struct RuleStrategy {
+    pub name: String,
+    pub entry: Condition,
+    pub exit: Condition,
+    pub sizing: PositionSizing,
+    indicators: Vec<Indicator>,
+    tables: HashMap<String, IndicatorTable>,
+}
+
+impl RuleStrategy {
+    pub fn new(name: &str, entry: Condition, exit: Condition, sizing: PositionSizing) -> Self {
+        let mut indicators = Condition::And(vec![entry.clone(), exit.clone()]).indicators();
+        if let PositionSizing::ATRRisk(_, period, _) = sizing {
+            indicators.push(Indicator::ATR(period));
+        }
+        Self {
+            name: name.to_string(),
+            entry,
+            exit,
+            sizing,
+            indicators,
+            tables: HashMap::new(),
+        }
+    }
+}
+
+impl Strategy for RuleStrategy {
+    fn gen_order_map(
+        &mut self,
+        timestamp: usize,
+        _dates: &[String],
+        chart_map: &HashMap<String, Chart>,
+        portfolio: &Portfolio,
+    ) -> HashMap<String, Order> {
+        let i = timestamp - 1;
+        let mut signal = HashMap::new();
+        let mut atr = HashMap::new();
+        for symbol in chart_map.keys() {
+            let table = match self.tables.get(symbol) {
+                Some(table) => table,
+                None => {
+                    signal.insert(symbol.to_string(), false);
+                    continue;
+                }
+            };
+            let holding = portfolio.get_share(symbol).unwrap_or(0) > 0;
+            let long = if holding {
+                !self.exit.evaluate(table, i)
+            } else {
+                self.entry.evaluate(table, i)
+            };
+            signal.insert(symbol.to_string(), long);
+            if let PositionSizing::ATRRisk(_, period, _) = self.sizing {
+                let column = format!("atr_{}", period);
+                atr.insert(symbol.to_string(), table.get(&column).unwrap()[i]);
+            }
+        }
+
+        signal_orders(&signal, chart_map, portfolio, |symbol, value| {
+            let price = chart_map.get(symbol).unwrap().adj_close;
+            match self.sizing {
+                PositionSizing::Shares(n) => price * n as f64,
+                PositionSizing::Amount(amount) => amount,
+                PositionSizing::Equity(fraction) => value * fraction,
+                PositionSizing::ATRRisk(risk, _, multiplier) => {
+                    let stop = atr.get(symbol).unwrap() * multiplier;
+                    if stop > 0f64 {
+                        value * risk / stop * price
+                    } else {
+                        0f64
+                    }
+                }
+            }
+        })
+    }
+
+    fn to_string(&self) -> String {
+        self.name.clone()
+    }
+
+    fn reset(&mut self) {
+        self.tables.clear();
+    }
+
+    fn prepare(&mut self, market_data: &MarketData) {
+        for (symbol, chart) in adjusted_history(market_data) {
+            let hist = HistoricalChart {
+                symbol: symbol.clone(),
+                date: market_data.date.clone(),
+                chart,
+            };
+            let table = IndicatorTable::new(&hist, &self.indicators);
+            self.tables.insert(symbol, table);
+        }
+    }
+
+    fn box_clone(&self) -> Box<dyn Strategy> {
+        Box::new(self.clone())
+    }
+}
+
+/// Current weight of each symbol in the portfolio value (the rest is cash)
+fn portfolio_weight(
+    chart_map: &HashMap<String, Chart>,
+    portfolio: &Portfolio,
+) -> HashMap<String, f64> {
+    let values = portfolio
+        .get_symbols()
+        .into_iter()
+        .map(|s| {
+            let price = chart_map.get(&s).unwrap().adj_close;
+            let value = price * portfolio.get_share(&s).unwrap() as f64;
+            (s, value)
+        })
+        .collect::<HashMap<String, f64>>();
+    let total = portfolio.value(chart_map);
+    values.into_iter().map(|(s, v)| (s, v / total)).collect()
+}
+
+/// Orders from the current holdings to the given weights of the portfolio value
+///
+/// - Symbols not in `weight` are left unchanged
+fn weight_orders(
+    weight: &HashMap<String, f64>,
+    chart_map: &HashMap<String, Chart>,
+    portfolio: &Portfolio,
+) -> HashMap<String, Order> {
+    let value = portfolio.value(chart_map);
+    portfolio
+        .get_symbols()
+        .iter()
+        .map(|symbol| {
+            let shares = match weight.get(symbol) {
+                Some(w) => {
+                    let price = chart_map.get(symbol).unwrap().adj_close;
+                    let target = (value * w / price) as isize;
+                    target - portfolio.get_share(symbol).unwrap()
+                }
+                None => 0,
+            };
+            (symbol.to_string(), Order::new(symbol, shares))
+        })
+        .collect()
+}
+
+/// `weight_orders` without orders smaller than `min_trade` (see `Strategy`)
+///
+/// - Every symbol of the portfolio has an order (0 if skipped or not in `weight`)
+fn delta_orders(
+    weight: &HashMap<String, f64>,
+    min_trade: f64,
+    chart_map: &HashMap<String, Chart>,
+    portfolio: &Portfolio,
+) -> HashMap<String, Order> {
+    let opening = portfolio.shares.values().all(|&share| share == 0);
+    weight_orders(weight, chart_map, portfolio)
+        .into_iter()
+        .map(|(symbol, order)| {
+            let price = chart_map.get(&symbol).unwrap().adj_close;
+            let order = if opening || (order.shares as f64 * price).abs() >= min_trade {
+                order
+            } else {
+                Order::new(&symbol, 0)
+            };
+            (symbol, order)
+        })
+        .collect()
+}
+
+/// Replace picks which do not beat the hurdle by the fallback (`None` for cash)
+fn absolute_filter(
+    picks: Vec<(String, f64)>,
+    hurdle: f64,
+    fallback: &Option<String>,
+) -> Vec<Option<String>> {
+    picks
+        .into_iter()
+        .map(|(symbol, score)| {
+            if score > hurdle {
+                Some(symbol)
+            } else {
+                fallback.clone()
+            }
+        })
+        .collect()
+}
+
+/// Adjusted bars of each symbol over the whole market data
+fn adjusted_history(market_data: &MarketData) -> HashMap<String, Vec<Chart>> {
+    let mut history: HashMap<String, Vec<Chart>> = HashMap::new();
+    for chart_map in market_data.chart.iter() {
+        for (symbol, chart) in chart_map.iter() {
+            history
+                .entry(symbol.to_string())
+                .or_default()
+                .push(chart.adjusted());
+        }
+    }
+    history
+}
+
+/// Append the current bars to the per-symbol history
+fn record_history(history: &mut HashMap<String, Vec<Chart>>, chart_map: &HashMap<String, Chart>) {
+    for (symbol, chart) in chart_map.iter() {
+        history.entry(symbol.to_string()).or_default().push(*chart);
+    }
+}
+
+/// Orders to follow long/flat signals
+///
+/// - entry_value : capital to enter a symbol given the portfolio value (bounded by the remaining balance)
+/// - Exit by closing the whole position
+fn signal_orders<F: Fn(&str, f64) -> f64>(
+    signal: &HashMap<String, bool>,
+    chart_map: &HashMap<String, Chart>,
+    portfolio: &Portfolio,
+    entry_value: F,
+) -> HashMap<String, Order> {
+    let symbols = portfolio.get_symbols();
+    let mut order_map = HashMap::new();
+    for symbol in symbols.iter() {
+        order_map.insert(symbol.to_string(), Order::new(symbol, 0));
+    }
+    let value = portfolio.value(chart_map);
+
+    // Exits first to release the balance for entries
+    let mut balance = portfolio.get_balance();
+    for symbol in symbols.iter() {
+        let share = portfolio.get_share(symbol).unwrap();
+        if share > 0 && !signal.get(symbol).cloned().unwrap_or(false) {
+            let price = chart_map.get(symbol).unwrap().adj_close;
+            balance += price * share as f64;
+            order_map.insert(symbol.to_string(), Order::new(symbol, -share));
+        }
+    }
+    for symbol in symbols.iter() {
+        let share = portfolio.get_share(symbol).unwrap();
+        if share == 0 && signal.get(symbol).cloned().unwrap_or(false) {
+            let price = chart_map.get(symbol).unwrap().adj_close;
+            let shares = (entry_value(symbol, value).min(balance) / price).max(0f64) as isize;
+            balance -= price * shares as f64;
+            order_map.insert(symbol.to_string(), Order::new(symbol, shares));
+        }
+    }
+    order_map
 }
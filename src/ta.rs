@@ -1,4 +1,6 @@
+use crate::api::{HistoricalChart, Timeframe};
 use peroxide::fuga::*;
+use serde::Deserialize;
 
 /// Simple Moving Average
 pub fn sma(v: &[f64], period: usize) -> Vec<f64> {
@@ -68,6 +70,149 @@ pub fn tema(v: &[f64], period: usize) -> Vec<f64> {
         .collect::<Vec<f64>>()
 }
 
+/// Moving Average Type
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+pub enum MAType {
+    SMA,
+    EMA,
+    WMA,
+    DEMA,
+    TEMA,
+    SMMA,
+    KAMA,
+    HMA,
+    ALMA,
+    T3,
+    ZLEMA,
+}
+
+/// Moving Average of given type
+pub fn ma(v: &[f64], period: usize, ma_type: MAType) -> Vec<f64> {
+    match ma_type {
+        MAType::SMA => sma(v, period),
+        MAType::EMA => ema(v, period),
+        MAType::WMA => wma(v, period),
+        MAType::DEMA => dema(v, period),
+        MAType::TEMA => tema(v, period),
+        MAType::SMMA => smma(v, period),
+        MAType::KAMA => kama(v, period),
+        MAType::HMA => hma(v, period),
+        MAType::ALMA => alma(v, period),
+        MAType::T3 => t3(v, period),
+        MAType::ZLEMA => zlema(v, period),
+    }
+}
+
+/// Kaufman Adaptive Moving Average
+///
+/// - Efficiency ratio over `period`, fast & slow constants of EMA(2) & EMA(30)
+pub fn kama(v: &[f64], period: usize) -> Vec<f64> {
+    let mut res = vec![0f64; v.len()];
+    if v.is_empty() {
+        return res;
+    }
+    let fast = 2f64 / (2f64 + 1f64);
+    let slow = 2f64 / (30f64 + 1f64);
+    res[0] = v[0];
+    for i in 1..v.len() {
+        let start = i.saturating_sub(period);
+        let change = (v[i] - v[start]).abs();
+        let volatility = (start + 1..=i)
+            .map(|j| (v[j] - v[j - 1]).abs())
+            .sum::<f64>();
+        let er = if volatility > 0f64 {
+            change / volatility
+        } else {
+            0f64
+        };
+        let sc = (er * (fast - slow) + slow).powi(2);
+        res[i] = res[i - 1] + sc * (v[i] - res[i - 1]);
+    }
+    res
+}
+
+/// Hull Moving Average
+pub fn hma(v: &[f64], period: usize) -> Vec<f64> {
+    let wma_half = wma(v, (period / 2).max(1));
+    let wma_full = wma(v, period);
+    let diff = zip_with(|x, y| 2f64 * x - y, &wma_half, &wma_full);
+    wma(&diff, ((period as f64).sqrt() as usize).max(1))
+}
+
+/// Arnaud Legoux Moving Average
+///
+/// - Gaussian weights with offset 0.85 and sigma 6
+pub fn alma(v: &[f64], period: usize) -> Vec<f64> {
+    let offset = 0.85f64;
+    let sigma = 6f64;
+    let mut res = vec![0f64; v.len()];
+    for i in 0..v.len() {
+        let window = period.min(i + 1);
+        let m = offset * (window - 1) as f64;
+        let s = window as f64 / sigma;
+        let mut numer = 0f64;
+        let mut denom = 0f64;
+        for j in 0..window {
+            let w = (-(j as f64 - m).powi(2) / (2f64 * s * s)).exp();
+            numer += w * v[i + 1 - window + j];
+            denom += w;
+        }
+        res[i] = numer / denom;
+    }
+    res
+}
+
+/// Tillson T3 Moving Average
+///
+/// - Six-fold EMA with volume factor 0.7
+pub fn t3(v: &[f64], period: usize) -> Vec<f64> {
+    let a = 0.7f64;
+    let c1 = -a.powi(3);
+    let c2 = 3f64 * a.powi(2) + 3f64 * a.powi(3);
+    let c3 = -6f64 * a.powi(2) - 3f64 * a - 3f64 * a.powi(3);
+    let c4 = 1f64 + 3f64 * a + a.powi(3) + 3f64 * a.powi(2);
+    let e1 = ema(v, period);
+    let e2 = ema(&e1, period);
+    let e3 = ema(&e2, period);
+    let e4 = ema(&e3, period);
+    let e5 = ema(&e4, period);
+    let e6 = ema(&e5, period);
+    (0..v.len())
+        .map(|i| c1 * e6[i] + c2 * e5[i] + c3 * e4[i] + c4 * e3[i])
+        .collect::<Vec<f64>>()
+}
+
+/// Volume Weighted Moving Average
+///
+/// - Falls back to the SMA of `v` where the window has no volume
+pub fn vwma(v: &[f64], volume: &[f64], period: usize) -> Vec<f64> {
+    let pv = zip_with(|x, y| x * y, v, volume);
+    let pv_ma = sma(&pv, period);
+    let volume_ma = sma(volume, period);
+    let v_ma = sma(v, period);
+    (0..v_ma.len())
+        .map(|i| {
+            if volume_ma[i] == 0f64 {
+                v_ma[i]
+            } else {
+                pv_ma[i] / volume_ma[i]
+            }
+        })
+        .collect()
+}
+
+/// Zero Lag Exponential Moving Average
+///
+/// - period should be positive
+pub fn zlema(v: &[f64], period: usize) -> Vec<f64> {
+    assert!(period > 0, "zlema: period should be positive");
+    let lag = (period - 1) / 2;
+    let de_lagged = (0..v.len())
+        .map(|i| 2f64 * v[i] - v[i.saturating_sub(lag)])
+        .collect::<Vec<f64>>();
+    ema(&de_lagged, period)
+}
+
 /// Williams %R
 pub fn williams_r(high: &[f64], low: &[f64], close: &[f64], period: usize) -> Vec<f64> {
     let mut res = vec![0f64; high.len()];
@@ -107,11 +252,210 @@ pub fn rsi(v: &[f64], period: usize) -> Vec<f64> {
     zip_with(|x, y| 100f64 * x / (x + y + 1e-3), &au, &ad)
 }
 
+/// Wilder's Running Moving Average
+///
+/// - Seeded with the simple average of the first `period` values
+pub fn rma(v: &[f64], period: usize) -> Vec<f64> {
+    let mut res = vec![0f64; v.len()];
+    let mut sum = 0f64;
+    for i in 0..v.len() {
+        if i < period {
+            sum += v[i];
+            res[i] = sum / (i + 1) as f64;
+        } else {
+            res[i] = (res[i - 1] * (period - 1) as f64 + v[i]) / period as f64;
+        }
+    }
+    res
+}
+
+/// Relative Strength Index with Wilder's original smoothing
+///
+/// - The first average gain & loss are simple averages of the first `period` changes
+pub fn wilder_rsi(v: &[f64], period: usize) -> Vec<f64> {
+    let mut u = vec![0f64; v.len()];
+    let mut d = vec![0f64; v.len()];
+    for i in 1..v.len() {
+        let diff = v[i] - v[i - 1];
+        if diff > 0.0 {
+            u[i] = diff;
+        } else {
+            d[i] = -diff;
+        }
+    }
+    // Changes start from index 1
+    let mut au = vec![0f64; v.len()];
+    let mut ad = vec![0f64; v.len()];
+    if v.len() > 1 {
+        au[1..].copy_from_slice(&rma(&u[1..], period));
+        ad[1..].copy_from_slice(&rma(&d[1..], period));
+    }
+    zip_with(
+        |x, y| {
+            if x + y == 0f64 {
+                50f64
+            } else {
+                100f64 * x / (x + y)
+            }
+        },
+        &au,
+        &ad,
+    )
+}
+
+/// Stochastic RSI
+///
+/// Return: (%K, %D)
+pub fn stoch_rsi(
+    v: &[f64],
+    rsi_period: usize,
+    stoch_period: usize,
+    k_smooth: usize,
+    d_smooth: usize,
+) -> (Vec<f64>, Vec<f64>) {
+    let rsi_ = wilder_rsi(v, rsi_period);
+    let highest_ = highest(&rsi_, stoch_period);
+    let lowest_ = lowest(&rsi_, stoch_period);
+    let stoch = (0..v.len())
+        .map(|i| {
+            let range = highest_[i] - lowest_[i];
+            if range > 0f64 {
+                (rsi_[i] - lowest_[i]) / range * 100f64
+            } else {
+                50f64
+            }
+        })
+        .collect::<Vec<f64>>();
+    let k = sma(&stoch, k_smooth);
+    let d = sma(&k, d_smooth);
+    (k, d)
+}
+
+/// True Strength Index
+///
+/// Return: (TSI, Signal)
+pub fn tsi(
+    v: &[f64],
+    long_period: usize,
+    short_period: usize,
+    signal_period: usize,
+) -> (Vec<f64>, Vec<f64>) {
+    let mut m = vec![0f64; v.len()];
+    for i in 1..v.len() {
+        m[i] = v[i] - v[i - 1];
+    }
+    let m_abs = m.fmap(|x| x.abs());
+    let numer = ema(&ema(&m, long_period), short_period);
+    let denom = ema(&ema(&m_abs, long_period), short_period);
+    let tsi = zip_with(
+        |x, y| if y > 0f64 { 100f64 * x / y } else { 0f64 },
+        &numer,
+        &denom,
+    );
+    let signal = ema(&tsi, signal_period);
+    (tsi, signal)
+}
+
+/// Ultimate Oscillator (usually 7, 14, 28)
+pub fn ultimate_oscillator(
+    high: &[f64],
+    low: &[f64],
+    close: &[f64],
+    period1: usize,
+    period2: usize,
+    period3: usize,
+) -> Vec<f64> {
+    let mut bp = vec![0f64; high.len()];
+    let mut tr = vec![0f64; high.len()];
+    for i in 0..high.len() {
+        let prev_close = if i > 0 { close[i - 1] } else { close[i] };
+        let true_low = low[i].min(prev_close);
+        bp[i] = close[i] - true_low;
+        tr[i] = high[i].max(prev_close) - true_low;
+    }
+    let avg = |period: usize| {
+        zip_with(
+            |x, y| if y > 0f64 { x / y } else { 0f64 },
+            &sma(&bp, period),
+            &sma(&tr, period),
+        )
+    };
+    let avg1 = avg(period1);
+    let avg2 = avg(period2);
+    let avg3 = avg(period3);
+    (0..high.len())
+        .map(|i| 100f64 * (4f64 * avg1[i] + 2f64 * avg2[i] + avg3[i]) / 7f64)
+        .collect::<Vec<f64>>()
+}
+
+/// Rate of Change (%)
+pub fn roc(v: &[f64], period: usize) -> Vec<f64> {
+    (0..v.len())
+        .map(|i| {
+            let prev = v[i.saturating_sub(period)];
+            100f64 * (v[i] - prev) / prev
+        })
+        .collect::<Vec<f64>>()
+}
+
+/// Momentum
+pub fn momentum(v: &[f64], period: usize) -> Vec<f64> {
+    (0..v.len())
+        .map(|i| v[i] - v[i.saturating_sub(period)])
+        .collect::<Vec<f64>>()
+}
+
+/// Awesome Oscillator (usually 5, 34)
+pub fn awesome_oscillator(high: &[f64], low: &[f64], period1: usize, period2: usize) -> Vec<f64> {
+    let hl2 = zip_with(|h, l| (h + l) / 2f64, high, low);
+    zip_with(|x, y| x - y, &sma(&hl2, period1), &sma(&hl2, period2))
+}
+
 /// Moving Average Convergence Divergence
-pub fn macd(v: &[f64], period1: usize, period2: usize) -> Vec<f64> {
-    let ema1 = ema(v, period1);
-    let ema2 = ema(v, period2);
-    zip_with(|x, y| x - y, &ema1, &ema2)
+///
+/// Return: (MACD, Signal, Histogram)
+pub fn macd(
+    v: &[f64],
+    period1: usize,
+    period2: usize,
+    signal_period: usize,
+    ma_type: MAType,
+) -> (Vec<f64>, Vec<f64>, Vec<f64>) {
+    let ma1 = ma(v, period1, ma_type);
+    let ma2 = ma(v, period2, ma_type);
+    let macd = zip_with(|x, y| x - y, &ma1, &ma2);
+    let signal = ma(&macd, signal_period, ma_type);
+    let hist = zip_with(|x, y| x - y, &macd, &signal);
+    (macd, signal, hist)
+}
+
+/// Percentage Price Oscillator
+///
+/// Return: (PPO, Signal, Histogram)
+pub fn ppo(
+    v: &[f64],
+    period1: usize,
+    period2: usize,
+    signal_period: usize,
+    ma_type: MAType,
+) -> (Vec<f64>, Vec<f64>, Vec<f64>) {
+    let ma1 = ma(v, period1, ma_type);
+    let ma2 = ma(v, period2, ma_type);
+    let ppo = zip_with(|x, y| 100f64 * (x - y) / y, &ma1, &ma2);
+    let signal = ma(&ppo, signal_period, ma_type);
+    let hist = zip_with(|x, y| x - y, &ppo, &signal);
+    (ppo, signal, hist)
+}
+
+/// Detrended Price Oscillator
+///
+/// - Compares price with the SMA of `period / 2 + 1` bars ago (no look-ahead)
+pub fn dpo(v: &[f64], period: usize) -> Vec<f64> {
+    let shift = period / 2 + 1;
+    let m = sma(v, period);
+    (0..v.len())
+        .map(|i| v[i] - m[i.saturating_sub(shift)])
+        .collect::<Vec<f64>>()
 }
 
 /// Average True Range
@@ -124,7 +468,7 @@ pub fn atr(high: &[f64], low: &[f64], close: &[f64], period: usize) -> Vec<f64>
     }
     let mut atr = vec![0f64; high.len()];
     let mut sum = 0f64;
-    for i in 0..period {
+    for i in 0..period.min(high.len()) {
         sum += tr[i];
         atr[i] = sum / (i + 1) as f64;
     }
@@ -142,6 +486,10 @@ pub fn adx_dmi(
     close: &[f64],
     period: usize,
 ) -> (Vec<f64>, Vec<f64>, Vec<f64>) {
+    if high.len() < 2 {
+        let nan = vec![f64::NAN; high.len()];
+        return (nan.clone(), nan.clone(), nan);
+    }
     let mut up_move = 0f64;
     let mut down_move = 0f64;
     let mut dm_plus = vec![0f64; high.len()];
@@ -214,9 +562,13 @@ pub fn stochastic(
     (k, d)
 }
 
-/// Divergence compute for any indicator
+/// Spline envelope through the peaks of local maxima for any indicator
 /// Return: (Value, Slope)
-pub fn divergence(v: &[f64]) -> (Vec<f64>, Vec<f64>) {
+pub fn peak_envelope(v: &[f64]) -> Result<(Vec<f64>, Vec<f64>), Box<dyn std::error::Error>> {
+    if v.len() < 3 {
+        return Err("peak_envelope: at least 3 values are required".into());
+    }
+
     // Find all local maxima
     let mut maxima = vec![];
     let mut max_idx = 0usize;
@@ -237,6 +589,10 @@ pub fn divergence(v: &[f64]) -> (Vec<f64>, Vec<f64>) {
     let (idx_f64, maxima): (Vec<f64>, Vec<f64>) =
         maxima.into_iter().map(|(x, y)| (x as f64, y)).unzip();
 
+    if maxima.is_empty() {
+        return Err("peak_envelope: no local maxima found".into());
+    }
+
     // Find all local maxima of local maxima
     let mut maxima2 = vec![];
     let mut max_idx = 0usize;
@@ -258,21 +614,152 @@ pub fn divergence(v: &[f64]) -> (Vec<f64>, Vec<f64>) {
         maxima2.into_iter().map(|(x, y)| (idx_f64[x], y)).unzip();
 
     // Insert first & last point if it is not included
-    if idx_f64[0] != 0f64 {
+    if idx_f64.first() != Some(&0f64) {
         idx_f64.insert(0, 0f64);
         maxima.insert(0, v[0]);
     }
-    if idx_f64[idx_f64.len() - 1] != v.len() as f64 - 1f64 {
+    if idx_f64.last() != Some(&(v.len() as f64 - 1f64)) {
         idx_f64.push(v.len() as f64 - 1f64);
         maxima.push(v[v.len() - 1]);
     }
+    if idx_f64.len() < 3 {
+        return Err("peak_envelope: not enough peaks to build a spline".into());
+    }
 
     // Create cubic spline of local maxima
     let cs = cubic_hermite_spline(&idx_f64, &maxima, Akima);
     let idx = seq(0, v.len() as u32 - 1, 1);
     let div = cs.eval_vec(&idx.fmap(|x| x as f64));
     let slope = cs.derivative().eval_vec(&idx.fmap(|x| x as f64));
-    (div, slope)
+    Ok((div, slope))
+}
+
+/// Swing high pivots
+///
+/// - `v[i]` is a pivot if it is the strict maximum of `v[i - lookback..=i + lookback]`
+/// - A pivot is only confirmed `lookback` bars after it occurs
+/// - Empty if `lookback` is 0 or `v` is shorter than `2 * lookback + 1`
+pub fn pivot_highs(v: &[f64], lookback: usize) -> Vec<usize> {
+    let mut pivots = vec![];
+    if lookback == 0 {
+        return pivots;
+    }
+    for i in lookback..v.len().saturating_sub(lookback) {
+        let window = &v[i - lookback..=i + lookback];
+        if window
+            .iter()
+            .enumerate()
+            .all(|(j, &x)| j == lookback || x < v[i])
+        {
+            pivots.push(i);
+        }
+    }
+    pivots
+}
+
+/// Swing low pivots
+///
+/// - `v[i]` is a pivot if it is the strict minimum of `v[i - lookback..=i + lookback]`
+/// - A pivot is only confirmed `lookback` bars after it occurs
+/// - Empty if `lookback` is 0 or `v` is shorter than `2 * lookback + 1`
+pub fn pivot_lows(v: &[f64], lookback: usize) -> Vec<usize> {
+    let mut pivots = vec![];
+    if lookback == 0 {
+        return pivots;
+    }
+    for i in lookback..v.len().saturating_sub(lookback) {
+        let window = &v[i - lookback..=i + lookback];
+        if window
+            .iter()
+            .enumerate()
+            .all(|(j, &x)| j == lookback || x > v[i])
+        {
+            pivots.push(i);
+        }
+    }
+    pivots
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DivergenceKind {
+    /// Price makes a lower low, indicator makes a higher low
+    RegularBullish,
+    /// Price makes a higher high, indicator makes a lower high
+    RegularBearish,
+    /// Price makes a higher low, indicator makes a lower low
+    HiddenBullish,
+    /// Price makes a lower high, indicator makes a higher high
+    HiddenBearish,
+}
+
+/// Divergence event between two consecutive price pivots
+///
+/// - `start`, `end` : indices of the two pivots
+/// - The event is only known at `end + lookback`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Divergence {
+    pub kind: DivergenceKind,
+    pub start: usize,
+    pub end: usize,
+}
+
+/// Divergence between price and any indicator (RSI, MACD, CCI, ...)
+///
+/// - Pivots are found on price with `lookback` bars on each side,
+///   and the indicator is compared at the same indices
+pub fn divergence(
+    price: &[f64],
+    indicator: &[f64],
+    lookback: usize,
+) -> Result<Vec<Divergence>, Box<dyn std::error::Error>> {
+    if price.len() != indicator.len() {
+        return Err(format!(
+            "divergence: length mismatch (price: {}, indicator: {})",
+            price.len(),
+            indicator.len()
+        )
+        .into());
+    }
+    if lookback == 0 {
+        return Err("divergence: lookback should be positive".into());
+    }
+    if price.len() < 2 * lookback + 1 {
+        return Err(format!(
+            "divergence: at least {} values are required for lookback {}",
+            2 * lookback + 1,
+            lookback
+        )
+        .into());
+    }
+    if price.iter().chain(indicator.iter()).any(|x| !x.is_finite()) {
+        return Err("divergence: price and indicator should be finite".into());
+    }
+
+    let mut events = vec![];
+    for w in pivot_lows(price, lookback).windows(2) {
+        let (start, end) = (w[0], w[1]);
+        let kind = if price[end] < price[start] && indicator[end] > indicator[start] {
+            DivergenceKind::RegularBullish
+        } else if price[end] > price[start] && indicator[end] < indicator[start] {
+            DivergenceKind::HiddenBullish
+        } else {
+            continue;
+        };
+        events.push(Divergence { kind, start, end });
+    }
+    for w in pivot_highs(price, lookback).windows(2) {
+        let (start, end) = (w[0], w[1]);
+        let kind = if price[end] > price[start] && indicator[end] < indicator[start] {
+            DivergenceKind::RegularBearish
+        } else if price[end] < price[start] && indicator[end] > indicator[start] {
+            DivergenceKind::HiddenBearish
+        } else {
+            continue;
+        };
+        events.push(Divergence { kind, start, end });
+    }
+    events.sort_by_key(|e| (e.end, e.start));
+    Ok(events)
 }
 
 /// Commodity Channel Index
@@ -286,3 +773,842 @@ pub fn cci(high: &[f64], low: &[f64], close: &[f64], period: usize) -> Vec<f64>
     let d = sma(&deviation.fmap(|x| x.abs()), period);
     zip_with(|x, y| (x - y) / (0.015 * y), &deviation, &d)
 }
+
+/// Rolling highest value over the last `period` samples
+pub fn highest(v: &[f64], period: usize) -> Vec<f64> {
+    let mut res = vec![0f64; v.len()];
+    for i in 0..v.len() {
+        let start = (i + 1).saturating_sub(period);
+        res[i] = v[start..=i].iter().cloned().fold(f64::MIN, f64::max);
+    }
+    res
+}
+
+/// Rolling lowest value over the last `period` samples
+pub fn lowest(v: &[f64], period: usize) -> Vec<f64> {
+    let mut res = vec![0f64; v.len()];
+    for i in 0..v.len() {
+        let start = (i + 1).saturating_sub(period);
+        res[i] = v[start..=i].iter().cloned().fold(f64::MAX, f64::min);
+    }
+    res
+}
+
+/// Parabolic SAR (Stop And Reverse)
+///
+/// - step : acceleration factor increment (usually 0.02)
+/// - max_step : maximum acceleration factor (usually 0.2)
+pub fn parabolic_sar(high: &[f64], low: &[f64], step: f64, max_step: f64) -> Vec<f64> {
+    let n = high.len();
+    let mut res = vec![0f64; n];
+    if n == 0 {
+        return res;
+    }
+
+    // Initial trend from the directional movement of the first two bars
+    let mut up = n < 2 || high[1] - high[0] >= low[0] - low[1];
+    let mut af = step;
+    let (mut sar, mut ep) = if up {
+        (low[0], high[0])
+    } else {
+        (high[0], low[0])
+    };
+    res[0] = sar;
+
+    for i in 1..n {
+        sar += af * (ep - sar);
+        if up {
+            // SAR never penetrates the lows of the previous two bars
+            sar = sar.min(low[i - 1]);
+            if i >= 2 {
+                sar = sar.min(low[i - 2]);
+            }
+            if low[i] < sar {
+                up = false;
+                sar = ep.max(high[i]).max(high[i - 1]);
+                ep = low[i];
+                af = step;
+            } else if high[i] > ep {
+                ep = high[i];
+                af = (af + step).min(max_step);
+            }
+        } else {
+            // SAR never penetrates the highs of the previous two bars
+            sar = sar.max(high[i - 1]);
+            if i >= 2 {
+                sar = sar.max(high[i - 2]);
+            }
+            if high[i] > sar {
+                up = true;
+                sar = ep.min(low[i]).min(low[i - 1]);
+                ep = high[i];
+                af = step;
+            } else if low[i] < ep {
+                ep = low[i];
+                af = (af + step).min(max_step);
+            }
+        }
+        res[i] = sar;
+    }
+    res
+}
+
+/// SuperTrend
+///
+/// Return: (SuperTrend, Direction)
+/// - Direction : 1 for uptrend, -1 for downtrend
+pub fn supertrend(
+    high: &[f64],
+    low: &[f64],
+    close: &[f64],
+    period: usize,
+    multiplier: f64,
+) -> (Vec<f64>, Vec<f64>) {
+    let n = high.len();
+    let atr = atr(high, low, close, period);
+    let mut upper = vec![0f64; n];
+    let mut lower = vec![0f64; n];
+    let mut st = vec![0f64; n];
+    let mut dir = vec![1f64; n];
+    for i in 0..n {
+        let hl2 = (high[i] + low[i]) / 2f64;
+        let basic_upper = hl2 + multiplier * atr[i];
+        let basic_lower = hl2 - multiplier * atr[i];
+        if i == 0 {
+            upper[i] = basic_upper;
+            lower[i] = basic_lower;
+            st[i] = lower[i];
+            continue;
+        }
+
+        // Bands only tighten while price stays inside them
+        upper[i] = if basic_upper < upper[i - 1] || close[i - 1] > upper[i - 1] {
+            basic_upper
+        } else {
+            upper[i - 1]
+        };
+        lower[i] = if basic_lower > lower[i - 1] || close[i - 1] < lower[i - 1] {
+            basic_lower
+        } else {
+            lower[i - 1]
+        };
+
+        dir[i] = if dir[i - 1] > 0f64 {
+            if close[i] < lower[i] {
+                -1f64
+            } else {
+                1f64
+            }
+        } else if close[i] > upper[i] {
+            1f64
+        } else {
+            -1f64
+        };
+        st[i] = if dir[i] > 0f64 { lower[i] } else { upper[i] };
+    }
+    (st, dir)
+}
+
+/// Ichimoku Kinko Hyo
+///
+/// - `senkou_a`, `senkou_b` are shifted `displacement` bars forward,
+///   so the first `displacement` values are `NaN`
+/// - `chikou` is the close shifted `displacement` bars backward,
+///   so the last `displacement` values are `NaN` (do not use it as a signal without lag)
+#[derive(Debug, Clone)]
+pub struct Ichimoku {
+    pub tenkan: Vec<f64>,
+    pub kijun: Vec<f64>,
+    pub senkou_a: Vec<f64>,
+    pub senkou_b: Vec<f64>,
+    pub chikou: Vec<f64>,
+}
+
+/// Ichimoku Kinko Hyo (usually 9, 26, 52, 26)
+pub fn ichimoku(
+    high: &[f64],
+    low: &[f64],
+    close: &[f64],
+    tenkan_period: usize,
+    kijun_period: usize,
+    senkou_b_period: usize,
+    displacement: usize,
+) -> Ichimoku {
+    let n = high.len();
+    let midpoint = |period: usize| {
+        zip_with(
+            |h, l| (h + l) / 2f64,
+            &highest(high, period),
+            &lowest(low, period),
+        )
+    };
+    let tenkan = midpoint(tenkan_period);
+    let kijun = midpoint(kijun_period);
+    let span_a = zip_with(|x, y| (x + y) / 2f64, &tenkan, &kijun);
+    let span_b = midpoint(senkou_b_period);
+
+    let mut senkou_a = vec![f64::NAN; n];
+    let mut senkou_b = vec![f64::NAN; n];
+    let mut chikou = vec![f64::NAN; n];
+    if displacement < n {
+        senkou_a[displacement..].copy_from_slice(&span_a[..n - displacement]);
+        senkou_b[displacement..].copy_from_slice(&span_b[..n - displacement]);
+        chikou[..n - displacement].copy_from_slice(&close[displacement..]);
+    }
+
+    Ichimoku {
+        tenkan,
+        kijun,
+        senkou_a,
+        senkou_b,
+        chikou,
+    }
+}
+
+/// Aroon Indicator
+///
+/// Return: (Aroon Up, Aroon Down, Aroon Oscillator)
+pub fn aroon(high: &[f64], low: &[f64], period: usize) -> (Vec<f64>, Vec<f64>, Vec<f64>) {
+    let n = high.len();
+    let mut up = vec![0f64; n];
+    let mut down = vec![0f64; n];
+    for i in 0..n {
+        let start = i.saturating_sub(period);
+        let mut high_idx = start;
+        let mut low_idx = start;
+        for j in start..=i {
+            if high[j] >= high[high_idx] {
+                high_idx = j;
+            }
+            if low[j] <= low[low_idx] {
+                low_idx = j;
+            }
+        }
+        up[i] = 100f64 * (period - (i - high_idx)) as f64 / period as f64;
+        down[i] = 100f64 * (period - (i - low_idx)) as f64 / period as f64;
+    }
+    let osc = zip_with(|x, y| x - y, &up, &down);
+    (up, down, osc)
+}
+
+/// Project higher timeframe values onto the base bars without look-ahead
+///
+/// - `period_index` : output of `HistoricalChart::period_index`
+/// - Each base bar sees the value of the last *closed* higher timeframe bar,
+///   so bars in the first period are `NaN`
+pub fn project_higher_timeframe(period_index: &[usize], values: &[f64]) -> Vec<f64> {
+    period_index
+        .iter()
+        .map(|&k| if k > 0 { values[k - 1] } else { f64::NAN })
+        .collect::<Vec<f64>>()
+}
+
+/// Evaluate any indicator on a higher timeframe and project it onto each bar
+///
+/// - e.g. weekly MACD : `|h| macd(&h.get_close_vec(), 12, 26, 9, MAType::EMA).0`
+pub fn higher_timeframe<F>(
+    hist: &HistoricalChart,
+    timeframe: Timeframe,
+    indicator: F,
+) -> Result<Vec<f64>, Box<dyn std::error::Error>>
+where
+    F: Fn(&HistoricalChart) -> Vec<f64>,
+{
+    let idx = hist.period_index(timeframe)?;
+    let values = indicator(&hist.resample(timeframe)?);
+    Ok(project_higher_timeframe(&idx, &values))
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PivotMethod {
+    Classic,
+    Fibonacci,
+    Camarilla,
+    Woodie,
+}
+
+/// Pivot point levels for each bar
+#[derive(Debug, Clone)]
+pub struct PivotPoints {
+    pub pp: Vec<f64>,
+    pub r1: Vec<f64>,
+    pub r2: Vec<f64>,
+    pub r3: Vec<f64>,
+    pub s1: Vec<f64>,
+    pub s2: Vec<f64>,
+    pub s3: Vec<f64>,
+}
+
+/// Pivot levels from a single bar
+///
+/// Return: [PP, R1, R2, R3, S1, S2, S3]
+pub fn pivot_levels(high: f64, low: f64, close: f64, method: PivotMethod) -> [f64; 7] {
+    let range = high - low;
+    match method {
+        PivotMethod::Classic => {
+            let pp = (high + low + close) / 3f64;
+            [
+                pp,
+                2f64 * pp - low,
+                pp + range,
+                high + 2f64 * (pp - low),
+                2f64 * pp - high,
+                pp - range,
+                low - 2f64 * (high - pp),
+            ]
+        }
+        PivotMethod::Fibonacci => {
+            let pp = (high + low + close) / 3f64;
+            [
+                pp,
+                pp + 0.382 * range,
+                pp + 0.618 * range,
+                pp + range,
+                pp - 0.382 * range,
+                pp - 0.618 * range,
+                pp - range,
+            ]
+        }
+        PivotMethod::Camarilla => {
+            let pp = (high + low + close) / 3f64;
+            [
+                pp,
+                close + range * 1.1 / 12f64,
+                close + range * 1.1 / 6f64,
+                close + range * 1.1 / 4f64,
+                close - range * 1.1 / 12f64,
+                close - range * 1.1 / 6f64,
+                close - range * 1.1 / 4f64,
+            ]
+        }
+        PivotMethod::Woodie => {
+            let pp = (high + low + 2f64 * close) / 4f64;
+            [
+                pp,
+                2f64 * pp - low,
+                pp + range,
+                high + 2f64 * (pp - low),
+                2f64 * pp - high,
+                pp - range,
+                low - 2f64 * (high - pp),
+            ]
+        }
+    }
+}
+
+/// Pivot points of the previous `timeframe` period, projected onto each bar
+///
+/// - Levels during the first period are `NaN`
+pub fn pivot_points(
+    hist: &HistoricalChart,
+    timeframe: Timeframe,
+    method: PivotMethod,
+) -> Result<PivotPoints, Box<dyn std::error::Error>> {
+    let idx = hist.period_index(timeframe)?;
+    let period_chart = hist.resample(timeframe)?;
+    let levels = period_chart
+        .get_charts()
+        .iter()
+        .map(|c| pivot_levels(c.high, c.low, c.close, method))
+        .collect::<Vec<[f64; 7]>>();
+
+    let n = idx.len();
+    let mut res = PivotPoints {
+        pp: vec![f64::NAN; n],
+        r1: vec![f64::NAN; n],
+        r2: vec![f64::NAN; n],
+        r3: vec![f64::NAN; n],
+        s1: vec![f64::NAN; n],
+        s2: vec![f64::NAN; n],
+        s3: vec![f64::NAN; n],
+    };
+    for (i, &k) in idx.iter().enumerate() {
+        if k == 0 {
+            continue;
+        }
+        let [pp, r1, r2, r3, s1, s2, s3] = levels[k - 1];
+        res.pp[i] = pp;
+        res.r1[i] = r1;
+        res.r2[i] = r2;
+        res.r3[i] = r3;
+        res.s1[i] = s1;
+        res.s2[i] = s2;
+        res.s3[i] = s3;
+    }
+    Ok(res)
+}
+
+/// Clustered price zone
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PriceZone {
+    pub level: f64,
+    pub lower: f64,
+    pub upper: f64,
+    pub touches: usize,
+}
+
+/// Support & Resistance zones from clustering swing highs & lows
+///
+/// - tolerance : relative distance to merge levels into a zone (e.g. 0.01 for 1%)
+/// - Swing points are confirmed `lookback` bars later,
+///   so pass only the bars before the current one in a backtest
+/// - Non-finite swing levels are ignored
+/// - Return zones sorted by level
+pub fn support_resistance(
+    high: &[f64],
+    low: &[f64],
+    lookback: usize,
+    tolerance: f64,
+) -> Vec<PriceZone> {
+    let mut levels = pivot_highs(high, lookback)
+        .into_iter()
+        .map(|i| high[i])
+        .chain(pivot_lows(low, lookback).into_iter().map(|i| low[i]))
+        .filter(|x| x.is_finite())
+        .collect::<Vec<f64>>();
+    levels.sort_by(|a, b| a.total_cmp(b));
+
+    let mut zones: Vec<PriceZone> = vec![];
+    for x in levels {
+        match zones.last_mut() {
+            Some(zone) if (x - zone.level).abs() <= tolerance * zone.level.abs() => {
+                zone.level = (zone.level * zone.touches as f64 + x) / (zone.touches + 1) as f64;
+                zone.upper = zone.upper.max(x);
+                zone.touches += 1;
+            }
+            _ => zones.push(PriceZone {
+                level: x,
+                lower: x,
+                upper: x,
+                touches: 1,
+            }),
+        }
+    }
+    zones
+}
+
+/// Rolling (sample) Standard Deviation
+///
+/// - 0 for windows of a single value (e.g. period < 2)
+pub fn rolling_std(v: &[f64], period: usize) -> Vec<f64> {
+    let mut res = vec![0f64; v.len()];
+    for i in 1..v.len() {
+        let start = (i + 1).saturating_sub(period);
+        if i > start {
+            res[i] = v[start..=i].to_vec().sd();
+        }
+    }
+    res
+}
+
+/// Rolling Z-Score
+pub fn zscore(v: &[f64], period: usize) -> Vec<f64> {
+    let m = sma(v, period);
+    let s = rolling_std(v, period);
+    (0..v.len())
+        .map(|i| {
+            if s[i] > 0f64 {
+                (v[i] - m[i]) / s[i]
+            } else {
+                0f64
+            }
+        })
+        .collect::<Vec<f64>>()
+}
+
+/// Centered second moments of a window
+///
+/// Return: (Sxx, Sxy, Syy)
+fn co_moments(x: &[f64], y: &[f64]) -> (f64, f64, f64) {
+    let x_mean = x.to_vec().mean();
+    let y_mean = y.to_vec().mean();
+    let mut sxx = 0f64;
+    let mut sxy = 0f64;
+    let mut syy = 0f64;
+    for (xi, yi) in x.iter().zip(y.iter()) {
+        sxx += (xi - x_mean).powi(2);
+        sxy += (xi - x_mean) * (yi - y_mean);
+        syy += (yi - y_mean).powi(2);
+    }
+    (sxx, sxy, syy)
+}
+
+/// Ordinary least squares fit of y = slope * x + intercept
+///
+/// Return: (Slope, Intercept, R²)
+fn linear_fit(x: &[f64], y: &[f64]) -> (f64, f64, f64) {
+    let (sxx, sxy, syy) = co_moments(x, y);
+    let y_mean = y.to_vec().mean();
+    if sxx == 0f64 {
+        return (0f64, y_mean, 0f64);
+    }
+    let slope = sxy / sxx;
+    let intercept = y_mean - slope * x.to_vec().mean();
+    let r2 = if syy > 0f64 {
+        sxy * sxy / (sxx * syy)
+    } else {
+        1f64
+    };
+    (slope, intercept, r2)
+}
+
+/// Rolling Linear Regression against bar index
+///
+/// Return: (Slope, Intercept, R²)
+/// - Intercept is the fitted value at the oldest bar of each window
+/// - All 0 if `period` is 0
+pub fn linreg(v: &[f64], period: usize) -> (Vec<f64>, Vec<f64>, Vec<f64>) {
+    let mut slope = vec![0f64; v.len()];
+    let mut intercept = vec![0f64; v.len()];
+    let mut r2 = vec![0f64; v.len()];
+    if period == 0 {
+        return (slope, intercept, r2);
+    }
+    for i in 0..v.len() {
+        let start = (i + 1).saturating_sub(period);
+        let x = (0..=i - start).map(|j| j as f64).collect::<Vec<f64>>();
+        let (s, b, r) = linear_fit(&x, &v[start..=i]);
+        slope[i] = s;
+        intercept[i] = b;
+        r2[i] = r;
+    }
+    (slope, intercept, r2)
+}
+
+/// Hurst Exponent from the scaling of RMS lagged differences
+///
+/// - H < 0.5 : mean-reverting, H = 0.5 : random walk, H > 0.5 : trending
+/// - `NaN` if `v` is not longer than `max_lag` or `max_lag < 3`
+pub fn hurst(v: &[f64], max_lag: usize) -> f64 {
+    if max_lag < 3 || v.len() <= max_lag {
+        return f64::NAN;
+    }
+    let mut log_lag = vec![];
+    let mut log_tau = vec![];
+    for lag in 2..max_lag {
+        let diff = zip_with(|x, y| x - y, &v[lag..], &v[..v.len() - lag]);
+        let tau = (diff.fmap(|x| x * x).sum() / diff.len() as f64).sqrt();
+        if tau > 0f64 {
+            log_lag.push((lag as f64).ln());
+            log_tau.push(tau.ln());
+        }
+    }
+    if log_lag.len() < 2 {
+        return f64::NAN;
+    }
+    linear_fit(&log_lag, &log_tau).0
+}
+
+/// Rolling Hurst Exponent
+pub fn rolling_hurst(v: &[f64], period: usize, max_lag: usize) -> Vec<f64> {
+    (0..v.len())
+        .map(|i| hurst(&v[(i + 1).saturating_sub(period)..=i], max_lag))
+        .collect::<Vec<f64>>()
+}
+
+/// Rolling Pearson Correlation
+///
+/// - All `NaN` if `x` and `y` have different lengths
+pub fn rolling_correlation(x: &[f64], y: &[f64], period: usize) -> Vec<f64> {
+    if x.len() != y.len() {
+        return vec![f64::NAN; x.len()];
+    }
+    let mut res = vec![0f64; x.len()];
+    for i in 1..x.len() {
+        let start = (i + 1).saturating_sub(period);
+        let (sxx, sxy, syy) = co_moments(&x[start..=i], &y[start..=i]);
+        if sxx > 0f64 && syy > 0f64 {
+            res[i] = sxy / (sxx * syy).sqrt();
+        }
+    }
+    res
+}
+
+/// Rolling Beta of `asset` against `benchmark` (use returns for both)
+///
+/// - All `NaN` if `asset` and `benchmark` have different lengths
+pub fn rolling_beta(asset: &[f64], benchmark: &[f64], period: usize) -> Vec<f64> {
+    if asset.len() != benchmark.len() {
+        return vec![f64::NAN; asset.len()];
+    }
+    let mut res = vec![0f64; asset.len()];
+    for i in 1..asset.len() {
+        let start = (i + 1).saturating_sub(period);
+        res[i] = linear_fit(&benchmark[start..=i], &asset[start..=i]).0;
+    }
+    res
+}
+
+/// Parkinson Volatility (annualized)
+pub fn parkinson_volatility(high: &[f64], low: &[f64], period: usize) -> Vec<f64> {
+    let hl = zip_with(|h, l| (h / l).ln().powi(2), high, low);
+    let scale = 1f64 / (4f64 * 2f64.ln());
+    sma(&hl, period).fmap(|x| (scale * x * 252f64).sqrt())
+}
+
+/// Garman-Klass Volatility (annualized)
+pub fn garman_klass_volatility(
+    open: &[f64],
+    high: &[f64],
+    low: &[f64],
+    close: &[f64],
+    period: usize,
+) -> Vec<f64> {
+    let term = (0..high.len())
+        .map(|i| {
+            let hl = (high[i] / low[i]).ln();
+            let co = (close[i] / open[i]).ln();
+            0.5 * hl * hl - (2f64 * 2f64.ln() - 1f64) * co * co
+        })
+        .collect::<Vec<f64>>();
+    sma(&term, period).fmap(|x| (x.max(0f64) * 252f64).sqrt())
+}
+
+/// Rogers-Satchell Volatility (annualized)
+pub fn rogers_satchell_volatility(
+    open: &[f64],
+    high: &[f64],
+    low: &[f64],
+    close: &[f64],
+    period: usize,
+) -> Vec<f64> {
+    let term = rogers_satchell_term(open, high, low, close);
+    sma(&term, period).fmap(|x| (x.max(0f64) * 252f64).sqrt())
+}
+
+fn rogers_satchell_term(open: &[f64], high: &[f64], low: &[f64], close: &[f64]) -> Vec<f64> {
+    (0..high.len())
+        .map(|i| {
+            (high[i] / close[i]).ln() * (high[i] / open[i]).ln()
+                + (low[i] / close[i]).ln() * (low[i] / open[i]).ln()
+        })
+        .collect::<Vec<f64>>()
+}
+
+/// Yang-Zhang Volatility (annualized)
+///
+/// - Combines overnight (close to open), open to close and Rogers-Satchell variances
+pub fn yang_zhang_volatility(
+    open: &[f64],
+    high: &[f64],
+    low: &[f64],
+    close: &[f64],
+    period: usize,
+) -> Vec<f64> {
+    let n = high.len();
+    let mut overnight = vec![0f64; n];
+    for i in 1..n {
+        overnight[i] = (open[i] / close[i - 1]).ln();
+    }
+    let open_close = zip_with(|c, o| (c / o).ln(), close, open);
+    let var_o = rolling_std(&overnight, period).fmap(|x| x * x);
+    let var_c = rolling_std(&open_close, period).fmap(|x| x * x);
+    let var_rs = sma(&rogers_satchell_term(open, high, low, close), period);
+    let k = 0.34 / (1.34 + (period as f64 + 1f64) / (period as f64 - 1f64).max(1f64));
+    (0..n)
+        .map(|i| {
+            let var = var_o[i] + k * var_c[i] + (1f64 - k) * var_rs[i];
+            (var.max(0f64) * 252f64).sqrt()
+        })
+        .collect::<Vec<f64>>()
+}
+
+/// Exponentially Weighted Moving Average Volatility (annualized)
+///
+/// - lambda : decay factor (RiskMetrics uses 0.94 for daily data)
+pub fn ewma_volatility(close: &[f64], lambda: f64) -> Vec<f64> {
+    let mut res = vec![0f64; close.len()];
+    let mut var = 0f64;
+    for i in 1..close.len() {
+        let r = (close[i] / close[i - 1]).ln();
+        var = if i == 1 {
+            r * r
+        } else {
+            lambda * var + (1f64 - lambda) * r * r
+        };
+        res[i] = (var * 252f64).sqrt();
+    }
+    res
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Closes of the StockCharts RSI worked example (Wilder, 14 periods)
+    const CLOSE: [f64; 33] = [
+        44.3389, 44.0902, 44.1497, 43.6124, 44.3278, 44.8264, 45.0955, 45.4245, 45.8433, 46.0826,
+        45.8931, 46.0328, 45.6140, 46.2820, 46.2820, 46.0028, 46.0328, 46.4116, 46.2222, 45.6439,
+        46.2122, 46.2521, 45.7137, 46.4515, 45.7835, 45.3548, 44.0288, 44.1783, 44.2181, 44.5672,
+        43.4205, 42.6628, 43.1314,
+    ];
+
+    /// Published RSI(14) from the 15th close onward (2 decimals)
+    const RSI: [f64; 19] = [
+        70.53, 66.32, 66.55, 69.41, 66.36, 57.97, 62.93, 63.26, 56.06, 62.38, 54.71, 50.42, 39.99,
+        41.46, 41.87, 45.46, 37.30, 33.08, 37.77,
+    ];
+
+    /// Bars of the hand-worked UO, AO & TSI examples
+    const HIGH: [f64; 6] = [10.0, 11.0, 12.0, 11.5, 12.5, 13.0];
+    const LOW: [f64; 6] = [9.0, 10.0, 10.5, 10.0, 11.0, 12.0];
+    const BAR_CLOSE: [f64; 6] = [9.5, 10.5, 11.5, 10.5, 12.0, 12.5];
+
+    fn assert_close(actual: &[f64], expected: &[f64], tol: f64) {
+        assert_eq!(actual.len(), expected.len());
+        for (i, (a, e)) in actual.iter().zip(expected.iter()).enumerate() {
+            assert!(
+                (a - e).abs() <= tol,
+                "index {}: {} != {} (tol {})",
+                i,
+                a,
+                e,
+                tol
+            );
+        }
+    }
+
+    #[test]
+    fn wilder_rsi_matches_stockcharts() {
+        let rsi = wilder_rsi(&CLOSE, 14);
+        assert_close(&rsi[14..], &RSI, 0.01);
+    }
+
+    #[test]
+    fn stoch_rsi_matches_published_rsi() {
+        // (RSI - lowest RSI) / (highest RSI - lowest RSI) over 14 published RSI values
+        let (k, d) = stoch_rsi(&CLOSE, 14, 14, 1, 1);
+        let expected = [4.8134, 6.3902, 18.5928, 0.0, 0.0, 15.5401];
+        assert_close(&k[27..], &expected, 0.05);
+        assert_close(&d[27..], &expected, 0.05);
+    }
+
+    #[test]
+    fn roc_and_momentum_match_definition() {
+        let roc_ = roc(&CLOSE, 12);
+        assert_close(
+            &[roc_[12], roc_[20], roc_[32]],
+            &[2.8758, 0.8047, -6.6666],
+            1e-4,
+        );
+        let momentum_ = momentum(&CLOSE, 10);
+        assert_close(
+            &[momentum_[10], momentum_[20], momentum_[32]],
+            &[1.5542, 0.3191, -2.5823],
+            1e-4,
+        );
+    }
+
+    #[test]
+    fn ultimate_oscillator_matches_hand_worked() {
+        // BP = close - min(low, prev close), TR = max(high, prev close) - min(low, prev close)
+        let uo = ultimate_oscillator(&HIGH, &LOW, &BAR_CLOSE, 2, 3, 4);
+        assert_close(&uo[3..], &[52.2367, 58.5871, 62.3016], 1e-4);
+    }
+
+    #[test]
+    fn awesome_oscillator_matches_hand_worked() {
+        let ao = awesome_oscillator(&HIGH, &LOW, 2, 4);
+        assert_close(&ao[3..], &[0.5, 0.1875, 0.5625], 1e-4);
+    }
+
+    #[test]
+    fn tsi_matches_hand_worked() {
+        // EMA(2) of EMA(3) of momentum over EMA(2) of EMA(3) of |momentum|
+        let (tsi_, _) = tsi(&BAR_CLOSE, 3, 2, 2);
+        assert_close(&tsi_[1..], &[100.0, 100.0, 15.2941, 47.2914, 61.4973], 1e-4);
+        let (up, _) = tsi(&[1.0, 2.0, 3.0, 4.0, 5.0], 25, 13, 7);
+        assert_close(&up[1..], &[100.0; 4], 1e-9);
+    }
+
+    #[test]
+    fn pivots_of_degenerate_input_are_empty() {
+        let v = [1.0, 3.0, 2.0, 4.0, 1.0];
+        assert!(pivot_highs(&[], 2).is_empty());
+        assert!(pivot_lows(&[], 2).is_empty());
+        assert!(pivot_highs(&v, 0).is_empty());
+        assert!(pivot_lows(&v, 0).is_empty());
+        assert!(pivot_highs(&v, 3).is_empty());
+        assert!(pivot_lows(&v, 3).is_empty());
+        assert_eq!(pivot_highs(&v, 1), vec![1, 3]);
+        assert_eq!(pivot_lows(&v, 1), vec![2]);
+    }
+
+    #[test]
+    fn peak_envelope_rejects_degenerate_input() {
+        assert!(peak_envelope(&[]).is_err());
+        assert!(peak_envelope(&[1.0, 2.0]).is_err());
+        // No local maximum
+        assert!(peak_envelope(&[1.0, 2.0, 3.0, 4.0]).is_err());
+    }
+
+    #[test]
+    fn divergence_rejects_degenerate_input() {
+        let v = [5.0, 3.0, 5.0, 5.0, 5.0, 2.0, 5.0];
+        assert!(divergence(&[], &[], 1).is_err());
+        assert!(divergence(&v, &v[1..], 1).is_err());
+        assert!(divergence(&v, &v, 0).is_err());
+        assert!(divergence(&v, &v, 4).is_err());
+        let mut nan = v;
+        nan[3] = f64::NAN;
+        assert!(divergence(&nan, &v, 1).is_err());
+    }
+
+    #[test]
+    fn support_resistance_ignores_nan_levels() {
+        let high = [1.0, 3.0, 1.0, f64::NAN, 1.0, 3.03, 1.0];
+        let low = [1.0, 0.5, 1.0, f64::NAN, 1.0, 0.5, 1.0];
+        let zones = support_resistance(&high, &low, 1, 0.02);
+        let touches = zones.iter().map(|z| z.touches).collect::<Vec<usize>>();
+        let levels = zones.iter().map(|z| z.level).collect::<Vec<f64>>();
+        assert_eq!(touches, vec![2, 2]);
+        assert_close(&levels, &[0.5, 3.015], 1e-12);
+    }
+
+    #[test]
+    fn linreg_with_period_zero_is_zero() {
+        let (slope, intercept, r2) = linreg(&CLOSE, 0);
+        assert!(slope
+            .iter()
+            .chain(&intercept)
+            .chain(&r2)
+            .all(|&x| x == 0f64));
+    }
+
+    #[test]
+    fn paired_rolling_stats_reject_length_mismatch() {
+        let corr = rolling_correlation(&CLOSE, &CLOSE[1..], 5);
+        assert_eq!(corr.len(), CLOSE.len());
+        assert!(corr.iter().all(|x| x.is_nan()));
+        let beta = rolling_beta(&CLOSE[1..], &CLOSE, 5);
+        assert_eq!(beta.len(), CLOSE.len() - 1);
+        assert!(beta.iter().all(|x| x.is_nan()));
+    }
+
+    #[test]
+    fn rolling_std_of_single_values_is_zero() {
+        let v = [1.0, 2.0, 4.0];
+        assert_close(&rolling_std(&v, 1), &[0.0; 3], 0.0);
+        assert_close(&rolling_std(&v, 0), &[0.0; 3], 0.0);
+        assert_close(
+            &rolling_std(&v, 2),
+            &[0.0, 0.5f64.sqrt(), 2f64.sqrt()],
+            1e-12,
+        );
+    }
+
+    #[test]
+    fn divergence_finds_regular_bullish() {
+        let price = [5.0, 3.0, 5.0, 5.0, 5.0, 2.0, 5.0];
+        let indicator = [50.0, 30.0, 50.0, 50.0, 50.0, 35.0, 50.0];
+        let events = divergence(&price, &indicator, 1).unwrap();
+        assert_eq!(
+            events,
+            vec![Divergence {
+                kind: DivergenceKind::RegularBullish,
+                start: 1,
+                end: 5
+            }]
+        );
+    }
+}
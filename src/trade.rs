@@ -6,7 +6,7 @@ use std::collections::{HashMap, HashSet};
 #[derive(Debug, Clone)]
 pub struct Portfolio {
     pub balance: f64,
-    pub shares: HashMap<String, usize>,
+    pub shares: HashMap<String, isize>, // negative for short positions
 }
 
 impl Portfolio {
@@ -22,11 +22,11 @@ impl Portfolio {
         self.shares.keys().cloned().collect::<Vec<String>>()
     }
 
-    pub fn get_share(&self, symbol: &str) -> Option<usize> {
+    pub fn get_share(&self, symbol: &str) -> Option<isize> {
         self.shares.get(symbol).cloned()
     }
 
-    pub fn get_share_mut(&mut self, symbol: &str) -> Option<&mut usize> {
+    pub fn get_share_mut(&mut self, symbol: &str) -> Option<&mut isize> {
         self.shares.get_mut(symbol)
     }
 
@@ -34,13 +34,32 @@ impl Portfolio {
         self.balance
     }
 
-    pub fn update_share(&mut self, symbol: &str, share: usize) {
+    pub fn update_share(&mut self, symbol: &str, share: isize) {
         self.shares.insert(symbol.to_string(), share);
     }
 
     pub fn update_balance(&mut self, balance: f64) {
         self.balance = balance;
     }
+
+    /// Balance plus the market value of every position (at adj_close)
+    pub fn value(&self, chart_map: &HashMap<String, Chart>) -> f64 {
+        self.balance
+            + self
+                .shares
+                .iter()
+                .map(|(symbol, &share)| chart_map.get(symbol).unwrap().adj_close * share as f64)
+                .sum::<f64>()
+    }
+
+    /// Market value of the short positions (positive, at adj_close)
+    pub fn short_value(&self, chart_map: &HashMap<String, Chart>) -> f64 {
+        self.shares
+            .iter()
+            .filter(|(_, &share)| share < 0)
+            .map(|(symbol, &share)| -chart_map.get(symbol).unwrap().adj_close * share as f64)
+            .sum()
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -58,12 +77,20 @@ impl Order {
     }
 }
 
+/// - portfolio : read-only (`get_portfolio`), every `run` starts from the constructor's portfolio
+/// - interest_rate : annual rate earned by free cash
+/// - borrow_rate : annual rate paid when free cash is negative (leverage), `interest_rate` by default
+/// - Free cash is the balance less the market value of short positions: short sale proceeds are
+///   held as collateral and earn no interest (no separate stock borrow fee is charged)
+#[derive(Clone)]
 pub struct Backtester {
-    pub portfolio: Portfolio,
+    portfolio: Portfolio,
     pub strategy: Box<dyn Strategy>,
     pub market_data: MarketData,
     pub interest_rate: f64,
+    pub borrow_rate: f64,
     pub sec_fee: f64,
+    init_portfolio: Portfolio,
 }
 
 pub struct BacktestReport {
@@ -229,14 +256,46 @@ impl Backtester {
         sec_fee: f64,
     ) -> Result<Self, Box<dyn std::error::Error>> {
         let market_data = MarketData::new(symbols, from, to).await?;
+        Ok(Self::from_market_data(
+            symbols,
+            init_balance,
+            strategy,
+            market_data,
+            interest_rate,
+            sec_fee,
+        ))
+    }
+
+    /// Backtester on already downloaded data (e.g. to share one `MarketData` across runs)
+    pub fn from_market_data(
+        symbols: &[String],
+        init_balance: f64,
+        strategy: Box<dyn Strategy>,
+        market_data: MarketData,
+        interest_rate: f64,
+        sec_fee: f64,
+    ) -> Self {
         let portfolio = Portfolio::new(init_balance, symbols);
-        Ok(Self {
+        Self {
+            init_portfolio: portfolio.clone(),
             portfolio,
             strategy,
             market_data,
             interest_rate,
+            borrow_rate: interest_rate,
             sec_fee,
-        })
+        }
+    }
+
+    /// Restore the initial portfolio and the initial state of the strategy
+    pub fn reset(&mut self) {
+        self.portfolio = self.init_portfolio.clone();
+        self.strategy.reset();
+    }
+
+    /// Portfolio at the end of the last run
+    pub fn get_portfolio(&self) -> &Portfolio {
+        &self.portfolio
     }
 
     pub fn get_symbols(&self) -> Vec<String> {
@@ -247,22 +306,10 @@ impl Backtester {
         self.portfolio.balance
     }
 
-    pub fn get_share(&self, symbol: &str) -> Option<usize> {
+    pub fn get_share(&self, symbol: &str) -> Option<isize> {
         self.portfolio.get_share(symbol)
     }
 
-    pub fn get_share_mut(&mut self, symbol: &str) -> Option<&mut usize> {
-        self.portfolio.get_share_mut(symbol)
-    }
-
-    pub fn update_share(&mut self, symbol: &str, share: usize) {
-        self.portfolio.shares.insert(symbol.to_string(), share);
-    }
-
-    pub fn update_balance(&mut self, balance: f64) {
-        self.portfolio.update_balance(balance)
-    }
-
     pub fn get_chart(&self, timestamp: usize) -> &HashMap<String, Chart> {
         &self.market_data.chart[timestamp - 1]
     }
@@ -282,9 +329,12 @@ impl Backtester {
             let price = chart.get(symbol).as_ref().unwrap().adj_close;
             let current_share = self.get_share(symbol).unwrap();
             let order_share = o.shares;
-            let cost = price * (order_share as f64) * (1f64 + self.sec_fee);
-            self.update_balance(balance - cost);
-            self.update_share(symbol, (current_share as isize + order_share) as usize);
+            // Fee is charged on both buys and sells
+            let amount = price * (order_share as f64);
+            let cost = amount + amount.abs() * self.sec_fee;
+            self.portfolio.update_balance(balance - cost);
+            self.portfolio
+                .update_share(symbol, current_share + order_share);
         }
     }
 
@@ -298,25 +348,38 @@ impl Backtester {
         value
     }
 
+    /// Run from the initial state (previous runs do not affect the result)
     pub fn run(&mut self, rolling_window: usize) -> BacktestReport {
+        self.reset();
+        self.strategy.prepare(&self.market_data);
         let mut timestamp = 1usize;
         let mut daily_return = vec![0f64; self.market_data.len()];
         let mut balance_history = vec![0f64; self.market_data.len()];
         let interst_rate = self.get_interest_rate();
         let daily_interest = (1f64 + interst_rate).powf(1f64 / 252f64) - 1f64;
+        let daily_borrow = (1f64 + self.borrow_rate).powf(1f64 / 252f64) - 1f64;
 
         let mut total_value = self.obtain_value(timestamp);
         while timestamp <= self.market_data.len() {
             let idx = timestamp - 1;
             let chart_map = self.get_chart(timestamp).clone();
-            let order_map = self
-                .strategy
-                .gen_order_map(timestamp, &chart_map, &self.portfolio);
+            let order_map = self.strategy.gen_order_map(
+                timestamp,
+                &self.market_data.date[..timestamp],
+                &chart_map,
+                &self.portfolio,
+            );
             self.execute_order(&order_map, timestamp);
 
-            // Interest
+            // Interest on free cash (short proceeds excluded, negative pays the borrow rate)
             let balance = self.get_balance();
-            self.update_balance(balance * (1f64 + daily_interest));
+            let free = balance - self.portfolio.short_value(&chart_map);
+            let rate = if free >= 0f64 {
+                daily_interest
+            } else {
+                daily_borrow
+            };
+            self.portfolio.update_balance(balance + free * rate);
 
             let new_value = self.obtain_value(timestamp);
             daily_return[idx] = (new_value - total_value) / total_value;
@@ -335,8 +398,7 @@ impl Backtester {
 
         let mut rolling_volatility = vec![0f64; daily_return.len()];
         for i in rolling_window..daily_return.len() {
-            rolling_volatility[i] =
-                daily_return[i - rolling_window..i].to_vec().sd() * 252f64.sqrt();
+            rolling_volatility[i] = annualized_volatility(&daily_return[i - rolling_window..i]);
         }
 
         let mut rolling_sharpe_ratio = vec![0f64; daily_return.len()];
@@ -460,3 +522,8 @@ impl BacktestReport {
         dg
     }
 }
+
+/// Annualized volatility of daily returns
+pub fn annualized_volatility(daily_return: &[f64]) -> f64 {
+    daily_return.to_vec().sd() * 252f64.sqrt()
+}